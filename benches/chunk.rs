@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use pngme::chunk::Chunk;
+use pngme::chunk_type::ChunkType;
+use std::hint::black_box;
+use std::str::FromStr;
+
+const CHUNK_SIZE: usize = 10 * 1024 * 1024;
+
+fn large_chunk() -> Chunk {
+    let data = (0..CHUNK_SIZE).map(|i| i as u8).collect();
+    Chunk::new(ChunkType::from_str("RuSt").unwrap(), data)
+}
+
+fn parse(c: &mut Criterion) {
+    let bytes = large_chunk().as_bytes();
+    let mut group = c.benchmark_group("chunk");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("try_from 10 MB", |b| {
+        b.iter(|| Chunk::try_from(black_box(bytes.as_slice())).unwrap())
+    });
+    group.bench_function("from_reader 10 MB", |b| {
+        b.iter(|| Chunk::from_reader(&mut black_box(bytes.as_slice())).unwrap())
+    });
+    group.finish();
+}
+
+fn serialize(c: &mut Criterion) {
+    let chunk = large_chunk();
+    let mut group = c.benchmark_group("chunk");
+    group.throughput(Throughput::Bytes(chunk.length() as u64 + 12));
+    group.sample_size(20);
+
+    group.bench_function("as_bytes 10 MB", |b| {
+        b.iter(|| black_box(&chunk).as_bytes())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, serialize);
+criterion_main!(benches);
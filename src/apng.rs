@@ -0,0 +1,120 @@
+use crate::{PngError, Result};
+
+/// The fields of an APNG acTL chunk.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AnimationControl {
+    pub num_frames: u32,
+    /// How many times to loop the animation, 0 meaning forever.
+    pub num_plays: u32,
+}
+
+/// The fields of an APNG fcTL chunk.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FrameControl {
+    pub sequence_number: u32,
+    pub width: u32,
+    pub height: u32,
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub delay_num: u16,
+    pub delay_den: u16,
+    pub dispose_op: u8,
+    pub blend_op: u8,
+}
+
+impl FrameControl {
+    /// The frame delay in seconds. A zero denominator means 1/100 of a second.
+    pub fn delay_seconds(&self) -> f64 {
+        let den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den
+        };
+        f64::from(self.delay_num) / f64::from(den)
+    }
+
+    /// The name the APNG spec gives to `dispose_op`.
+    pub fn dispose_op_name(&self) -> &'static str {
+        match self.dispose_op {
+            0 => "none",
+            1 => "background",
+            2 => "previous",
+            _ => "unknown",
+        }
+    }
+
+    /// The name the APNG spec gives to `blend_op`.
+    pub fn blend_op_name(&self) -> &'static str {
+        match self.blend_op {
+            0 => "source",
+            1 => "over",
+            _ => "unknown",
+        }
+    }
+}
+
+fn be_u32(bytes: &[u8], start: usize) -> u32 {
+    u32::from_be_bytes([
+        bytes[start],
+        bytes[start + 1],
+        bytes[start + 2],
+        bytes[start + 3],
+    ])
+}
+
+/// Parses the 8 bytes of acTL chunk data.
+pub fn parse_actl(data: &[u8]) -> Result<AnimationControl> {
+    let data: &[u8; 8] = data
+        .try_into()
+        .map_err(|_| PngError::InvalidActl(data.len()))?;
+    Ok(AnimationControl {
+        num_frames: be_u32(data, 0),
+        num_plays: be_u32(data, 4),
+    })
+}
+
+/// Parses the 26 bytes of fcTL chunk data.
+pub fn parse_fctl(data: &[u8]) -> Result<FrameControl> {
+    let data: &[u8; 26] = data
+        .try_into()
+        .map_err(|_| PngError::InvalidFctl(data.len()))?;
+    Ok(FrameControl {
+        sequence_number: be_u32(data, 0),
+        width: be_u32(data, 4),
+        height: be_u32(data, 8),
+        x_offset: be_u32(data, 12),
+        y_offset: be_u32(data, 16),
+        delay_num: u16::from_be_bytes([data[20], data[21]]),
+        delay_den: u16::from_be_bytes([data[22], data[23]]),
+        dispose_op: data[24],
+        blend_op: data[25],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actl() {
+        let info = parse_actl(&[0, 0, 0, 12, 0, 0, 0, 0]).unwrap();
+        assert_eq!(info.num_frames, 12);
+        assert_eq!(info.num_plays, 0);
+        assert!(parse_actl(&[0; 7]).is_err());
+    }
+
+    #[test]
+    fn test_parse_fctl() {
+        let data = [
+            0, 0, 0, 1, 0, 0, 0, 100, 0, 0, 0, 75, 0, 0, 0, 5, 0, 0, 0, 6, 0, 1, 0, 0, 1, 1,
+        ];
+        let frame = parse_fctl(&data).unwrap();
+        assert_eq!(frame.sequence_number, 1);
+        assert_eq!((frame.width, frame.height), (100, 75));
+        assert_eq!((frame.x_offset, frame.y_offset), (5, 6));
+        assert_eq!(frame.delay_seconds(), 0.01);
+        assert_eq!(frame.dispose_op_name(), "background");
+        assert_eq!(frame.blend_op_name(), "over");
+        assert!(parse_fctl(&data[..25]).is_err());
+    }
+}
@@ -1,27 +1,418 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Environment variable naming the chunk type to use when none is given.
+pub const CHUNK_TYPE_ENV: &str = "PNGME_CHUNK_TYPE";
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  other error
+  2  file not found
+  3  invalid PNG
+  4  chunk not found
+  5  CRC mismatch
+  6  invalid command line arguments";
+
 #[derive(Parser)]
-#[command(author, version, about)]
+#[command(author, version, about, after_help = EXIT_CODES)]
 pub struct Args {
+    /// Suppress status messages, keeping command output and errors
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
-    Encode {
+    Encode(EncodeArgs),
+    Decode(DecodeArgs),
+    Remove(RemoveArgs),
+    BatchRemove {
+        dir: PathBuf,
+        chunk_type: String,
+    },
+    /// Count chunk types across every PNG in a directory
+    Survey {
+        dir: PathBuf,
+    },
+    Print(PrintArgs),
+    Copy {
+        src: String,
+        dst: String,
+        chunk_type: String,
+    },
+    Count {
+        path: String,
+        chunk_type: Option<String>,
+    },
+    Dimensions {
+        path: String,
+    },
+    Diff {
+        a: String,
+        b: String,
+    },
+    /// Write the raw data of the first chunk of TYPE to OUT, or - for standard output
+    Export {
         path: String,
         chunk_type: String,
-        message: String,
+        output: PathBuf,
+    },
+    ExtractAll {
+        path: String,
+        output: PathBuf,
+        #[arg(long)]
+        chunk_type: Option<String>,
+    },
+    Info {
+        chunk_type: String,
+    },
+    /// Append every non-standard chunk from OTHER to a copy of BASE
+    Merge {
+        base: String,
+        other: String,
+        #[arg(short, long)]
+        output: PathBuf,
     },
-    Decode {
+    /// Explain what the case of each chunk type letter means
+    Spec,
+    /// Edit a PNG interactively, writing it only on save
+    Repl {
+        path: String,
+    },
+    Replace {
         path: String,
         chunk_type: String,
+        message: String,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
-    Remove {
+    Rename {
         path: String,
         chunk_type: String,
+        new_type: String,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
-    Print {
+    Search {
         path: String,
+        query: String,
+        #[arg(short, long)]
+        ignore_case: bool,
     },
+    /// Print the PNG as base64
+    #[command(name = "tobase64")]
+    ToBase64 {
+        path: String,
+    },
+    /// Decode a base64 PNG, as printed by tobase64, back into a file
+    #[command(name = "frombase64")]
+    FromBase64 {
+        /// The base64 text file, or - for standard input
+        input: String,
+        output: PathBuf,
+    },
+    Strip {
+        path: String,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    Validate {
+        path: String,
+    },
+    Verify {
+        path: String,
+    },
+    /// Print a shell completion script
+    #[command(hide = true)]
+    Completions {
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct EncodeArgs {
+    pub path: String,
+    /// The chunk type. Defaults to PNGME_CHUNK_TYPE when the message comes from
+    /// --message-file, --hex or --message-env; a lone positional is always the type
+    pub chunk_type: Option<String>,
+    /// The message, or `-` to read raw bytes from standard input
+    #[arg(group = "content")]
+    pub message: Option<String>,
+    /// Read the message bytes from a file instead
+    #[arg(long, value_name = "PATH", group = "content")]
+    pub message_file: Option<PathBuf>,
+    /// Give the message bytes as a hex string instead
+    #[arg(long, value_name = "HEXSTRING", group = "content")]
+    pub hex: Option<String>,
+    /// Read the message from this environment variable, keeping it out of process lists
+    #[arg(long, value_name = "VARNAME", group = "content")]
+    pub message_env: Option<String>,
+    /// Additional TYPE=MESSAGE pairs to encode, may be repeated
+    #[arg(long = "chunk", value_name = "TYPE=MESSAGE", value_parser = parse_chunk_pair)]
+    pub chunks: Vec<(String, String)>,
+    #[arg(long)]
+    pub compress: bool,
+    #[arg(long)]
+    pub password: Option<String>,
+    /// Store tEXt messages as spec-compliant keyword/text pairs
+    #[arg(long, requires = "keyword", conflicts_with_all = ["compress", "password"])]
+    pub as_text: bool,
+    /// Keyword for --as-text, e.g. Comment or Author
+    #[arg(long)]
+    pub keyword: Option<String>,
+    #[arg(long)]
+    pub at: Option<usize>,
+    /// Allow standard chunk types and types that already exist in the file
+    #[arg(long)]
+    pub force: bool,
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Copy the original to <PATH>.bak before overwriting it
+    #[arg(long)]
+    pub backup: bool,
+    /// Print the byte layout and CRC of each new chunk
+    #[arg(short, long)]
+    pub verbose: bool,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Default)]
+pub struct DecodeArgs {
+    pub path: String,
+    /// Chunk type, or a regex matched against whole chunk type names.
+    /// Defaults to PNGME_CHUNK_TYPE
+    pub chunk_type: Option<String>,
+    #[arg(long)]
+    pub password: Option<String>,
+    #[arg(long, conflicts_with = "hex")]
+    pub raw: bool,
+    #[arg(long)]
+    pub hex: bool,
+    #[arg(long)]
+    pub all: bool,
+    /// Parse chunks even when their CRC does not match
+    #[arg(long)]
+    pub no_crc_check: bool,
+}
+
+impl DecodeArgs {
+    /// The chunk type or pattern to decode, falling back to `PNGME_CHUNK_TYPE`.
+    pub fn chunk_type(&self) -> std::io::Result<String> {
+        chunk_type_or_env(self.chunk_type.as_deref())
+    }
+}
+
+#[derive(clap::Args, Default)]
+pub struct PrintArgs {
+    pub path: String,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+    /// Only show chunk types that are not defined by the PNG spec
+    #[arg(long)]
+    pub custom_only: bool,
+    /// Disable colored output even when writing to a terminal
+    #[arg(long)]
+    pub no_color: bool,
+    /// Show chunk counts and byte totals per type instead of every chunk
+    #[arg(long, conflicts_with = "format")]
+    pub stats: bool,
+    /// Also decode APNG animation (acTL) and frame (fcTL) control chunks
+    #[arg(long, conflicts_with_all = ["stats", "format"])]
+    pub apng: bool,
+    /// Parse chunks even when their CRC does not match
+    #[arg(long)]
+    pub no_crc_check: bool,
+}
+
+#[derive(clap::Args)]
+pub struct RemoveArgs {
+    pub path: String,
+    /// The chunk type. Defaults to PNGME_CHUNK_TYPE
+    pub chunk_type: Option<String>,
+    /// Remove the chunk at this position instead of by type
+    #[arg(long, conflicts_with_all = ["chunk_type", "all"])]
+    pub index: Option<usize>,
+    #[arg(long)]
+    pub all: bool,
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Copy the original to <PATH>.bak before overwriting it
+    #[arg(long)]
+    pub backup: bool,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+impl RemoveArgs {
+    /// The chunk type to remove, falling back to `PNGME_CHUNK_TYPE`. `None` when
+    /// removing by index.
+    pub fn chunk_type(&self) -> std::io::Result<Option<String>> {
+        if self.index.is_some() {
+            return Ok(None);
+        }
+        chunk_type_or_env(self.chunk_type.as_deref()).map(Some)
+    }
+}
+
+/// `chunk_type`, or `PNGME_CHUNK_TYPE` when it wasn't given. This is done here
+/// rather than with clap's `env`, which would count the variable as
+/// conflicting with `remove --index`.
+fn chunk_type_or_env(chunk_type: Option<&str>) -> std::io::Result<String> {
+    chunk_type_or(chunk_type, std::env::var(CHUNK_TYPE_ENV).ok())
+}
+
+fn chunk_type_or(chunk_type: Option<&str>, default: Option<String>) -> std::io::Result<String> {
+    chunk_type
+        .map(str::to_owned)
+        .or(default)
+        .ok_or_else(|| invalid_input("a chunk type is required"))
+}
+
+fn invalid_input(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+impl EncodeArgs {
+    /// The chunk type and message given on the command line, if any.
+    pub fn positional(&self) -> std::io::Result<Option<(String, Option<&str>)>> {
+        self.positional_or(std::env::var(CHUNK_TYPE_ENV).ok())
+    }
+
+    /// Like [`EncodeArgs::positional`], but with `default` as the chunk type used
+    /// when the message comes from a flag and no type is given.
+    fn positional_or(
+        &self,
+        default: Option<String>,
+    ) -> std::io::Result<Option<(String, Option<&str>)>> {
+        let has_content =
+            self.message_file.is_some() || self.hex.is_some() || self.message_env.is_some();
+        match (self.chunk_type.as_deref(), self.message.as_deref()) {
+            (Some(chunk_type), Some(message)) => Ok(Some((chunk_type.into(), Some(message)))),
+            (Some(chunk_type), None) if has_content => Ok(Some((chunk_type.into(), None))),
+            (Some(_), None) => Err(invalid_input("a message is required")),
+            (None, _) if has_content => Ok(Some((chunk_type_or(None, default)?, None))),
+            (None, _) if self.chunks.is_empty() => Err(invalid_input("a chunk type is required")),
+            (None, _) => Ok(None),
+        }
+    }
+
+    /// The positional type/message pair followed by every `--chunk` pair.
+    pub fn messages(&self) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+        let mut messages = Vec::new();
+        if let Some((chunk_type, message)) = self.positional()? {
+            let message = match (message, &self.message_file, &self.hex, &self.message_env) {
+                (_, Some(path), _, _) => std::fs::read(path)?,
+                (_, _, Some(hex), _) => parse_hex(hex).map_err(|e| invalid_input(&e))?,
+                (_, _, _, Some(name)) => std::env::var_os(name)
+                    .ok_or_else(|| {
+                        invalid_input(&format!("environment variable {} is not set", name))
+                    })?
+                    .into_encoded_bytes(),
+                (Some("-"), None, None, None) => {
+                    let mut data = Vec::new();
+                    std::io::stdin().lock().read_to_end(&mut data)?;
+                    data
+                }
+                (Some(message), None, None, None) => message.as_bytes().to_vec(),
+                (None, None, None, None) => Vec::new(),
+            };
+            messages.push((chunk_type, message));
+        }
+        for (chunk_type, message) in &self.chunks {
+            messages.push((chunk_type.clone(), message.as_bytes().to_vec()));
+        }
+        Ok(messages)
+    }
+}
+
+fn parse_chunk_pair(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(chunk_type, message)| (chunk_type.to_string(), message.to_string()))
+        .ok_or_else(|| format!("expected TYPE=MESSAGE, got '{}'", value))
+}
+
+fn parse_hex(value: &str) -> Result<Vec<u8>, String> {
+    if !value.len().is_multiple_of(2) {
+        return Err(format!("hex string has odd length {}", value.len()));
+    }
+    value
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| format!("invalid hex digits in '{}'", value))
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("00ff7A").unwrap(), vec![0x00, 0xff, 0x7a]);
+        assert_eq!(parse_hex("").unwrap(), Vec::<u8>::new());
+        assert!(parse_hex("abc").is_err());
+        assert!(parse_hex("zz").is_err());
+        assert!(parse_hex("+1").is_err());
+    }
+
+    fn encode_args(args: &[&str]) -> EncodeArgs {
+        match Args::parse_from(["pngme", "encode", "img.png"].iter().chain(args)).command {
+            Commands::Encode(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_positional_default_chunk_type() {
+        let default = || Some("ruSt".to_string());
+        // A lone positional is the chunk type, never the message.
+        assert!(encode_args(&["hello"]).positional_or(default()).is_err());
+
+        let args = encode_args(&["RuSt", "hello"]);
+        assert_eq!(
+            args.positional_or(default()).unwrap(),
+            Some(("RuSt".to_string(), Some("hello")))
+        );
+
+        let args = encode_args(&["--hex", "ff"]);
+        assert_eq!(
+            args.positional_or(default()).unwrap(),
+            Some(("ruSt".to_string(), None))
+        );
+        assert!(args.positional_or(None).is_err());
+        assert!(encode_args(&[]).positional_or(None).is_err());
+        assert_eq!(
+            encode_args(&["--chunk", "ruSt=hi"])
+                .positional_or(default())
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_chunk_type_or() {
+        let default = || Some("ruSt".to_string());
+        assert_eq!(chunk_type_or(Some("abCd"), default()).unwrap(), "abCd");
+        assert_eq!(chunk_type_or(None, default()).unwrap(), "ruSt");
+        assert!(chunk_type_or(None, None).is_err());
+    }
 }
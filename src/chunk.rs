@@ -1,10 +1,32 @@
+use crate::parse::ParseOptions;
 use crate::Result;
-use crate::{chunk_type::ChunkType, Error};
+use crate::{chunk_type::ChunkType, PngError};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
 use crc::{Crc, CRC_32_ISO_HDLC};
-use std::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use std::io::{self, Read};
 
 const CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-#[allow(dead_code)]
+
+fn slice_4(bytes: &[u8], start: usize) -> Result<[u8; 4]> {
+    bytes
+        .get(start..start + 4)
+        .and_then(|b| b.try_into().ok())
+        .ok_or(PngError::Truncated)
+}
+
+#[cfg(feature = "std")]
+fn read_4<R: Read>(reader: &mut R) -> Result<[u8; 4]> {
+    let mut bytes = Vec::with_capacity(4);
+    reader.by_ref().take(4).read_to_end(&mut bytes)?;
+    bytes.as_slice().try_into().map_err(|_| PngError::Truncated)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Chunk {
     length: u32,
     chunk_type: ChunkType,
@@ -13,40 +35,116 @@ pub struct Chunk {
 }
 
 impl TryFrom<&[u8]> for Chunk {
-    type Error = Error;
+    type Error = PngError;
 
     fn try_from(value: &[u8]) -> Result<Self> {
-        let mut iter = value.iter().copied();
+        Chunk::parse_with_options(value, &ParseOptions::default())
+    }
+}
 
-        let first4: [u8; 4] = iter
-            .by_ref()
-            .take(4)
-            .collect::<Vec<u8>>()
+impl TryFrom<Vec<u8>> for Chunk {
+    type Error = PngError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self> {
+        Chunk::try_from(value.as_slice())
+    }
+}
+
+impl Display for Chunk {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.data))
+    }
+}
+
+impl Chunk {
+    /// Like `Chunk::try_from`, but parsed according to `options`.
+    pub fn parse_with_options(value: &[u8], options: &ParseOptions) -> Result<Chunk> {
+        let length = u32::from_be_bytes(slice_4(value, 0)?);
+        let chunk_type = ChunkType::try_from(slice_4(value, 4)?)?;
+
+        // Check before copying so a forged length can't drive a huge allocation.
+        let rest = &value[8..];
+        if (rest.len() as u64) < u64::from(length) + 4 {
+            return Err(PngError::LengthExceedsData);
+        }
+        let (data, crc) = rest.split_at(length as usize);
+
+        let crc = u32::from_be_bytes(slice_4(crc, 0)?);
+        Chunk::from_parts(length, chunk_type, data.to_vec(), crc, options)
+    }
+
+    /// Reads a single chunk from `reader`, verifying its CRC.
+    ///
+    /// If the reader is already exhausted before the first byte of the chunk,
+    /// the error is an [`io::ErrorKind::UnexpectedEof`] `io::Error`; see
+    /// [`Chunk::is_end_of_stream`]. A chunk cut off part way through yields
+    /// a different error.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Chunk> {
+        Chunk::from_reader_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Like [`Chunk::from_reader`], but parsed according to `options`.
+    #[cfg(feature = "std")]
+    pub fn from_reader_with_options<R: Read>(
+        reader: &mut R,
+        options: &ParseOptions,
+    ) -> Result<Chunk> {
+        let mut length_bytes = Vec::with_capacity(4);
+        reader.by_ref().take(4).read_to_end(&mut length_bytes)?;
+        if length_bytes.is_empty() {
+            return Err(PngError::Io(io::Error::from(io::ErrorKind::UnexpectedEof)));
+        }
+        let length_bytes: [u8; 4] = length_bytes
             .as_slice()
             .try_into()
-            .expect("Failed to convert to [u8; 4]");
-        let length = u32::from_be_bytes(first4);
-
-        let chunk_type_bytes: [u8; 4] = iter
+            .map_err(|_| PngError::Truncated)?;
+        let length = u32::from_be_bytes(length_bytes);
+        let chunk_type = ChunkType::try_from(read_4(reader)?)?;
+
+        // Reading through `take` grows the buffer as bytes arrive, so a forged
+        // length can't force a huge up-front allocation.
+        let mut data = Vec::new();
+        reader
             .by_ref()
-            .take(4)
-            .collect::<Vec<u8>>()
-            .as_slice()
-            .try_into()?;
-        let chunk_type = ChunkType::try_from(chunk_type_bytes)?;
-        let data: Vec<u8> = iter.by_ref().take(length as usize).collect();
+            .take(u64::from(length))
+            .read_to_end(&mut data)?;
+        if data.len() as u64 != u64::from(length) {
+            return Err(PngError::LengthExceedsData);
+        }
 
-        let crc_bytes = iter
-            .by_ref()
-            .take(4)
-            .collect::<Vec<u8>>()
-            .as_slice()
-            .try_into()?;
-        let crc = u32::from_be_bytes(crc_bytes);
-        let calculated_crc = CRC.checksum(&Chunk::get_bytes_for_crc(&chunk_type, &data));
+        let crc = u32::from_be_bytes(read_4(reader)?);
+        Chunk::from_parts(length, chunk_type, data, crc, options)
+    }
+
+    /// Like `Chunk::try_from`, but fails if `bytes` holds more than one chunk.
+    pub fn try_from_exact(bytes: &[u8]) -> Result<Chunk> {
+        let chunk = Chunk::try_from(bytes)?;
+        match bytes.len() - (chunk.data_len() + 12) {
+            0 => Ok(chunk),
+            extra => Err(PngError::TrailingBytes(extra)),
+        }
+    }
+
+    /// Whether `error` is the end-of-stream signal returned by [`Chunk::from_reader`].
+    #[cfg(feature = "std")]
+    pub fn is_end_of_stream(error: &PngError) -> bool {
+        matches!(error, PngError::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof)
+    }
 
-        if calculated_crc != crc {
-            Err(Error::from("Invalid CRC"))
+    fn from_parts(
+        length: u32,
+        chunk_type: ChunkType,
+        data: Vec<u8>,
+        crc: u32,
+        options: &ParseOptions,
+    ) -> Result<Chunk> {
+        let expected = Chunk::checksum(&chunk_type, &data);
+        if options.verify_crc && expected != crc {
+            Err(PngError::CrcMismatch {
+                expected,
+                actual: crc,
+            })
         } else {
             Ok(Chunk {
                 length,
@@ -56,60 +154,78 @@ impl TryFrom<&[u8]> for Chunk {
             })
         }
     }
-}
-
-impl Display for Chunk {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.data_as_string().unwrap())
-    }
-}
-
-#[allow(dead_code)]
-impl Chunk {
-    pub fn get_bytes_for_crc(chunk_type: &ChunkType, data: &Vec<u8>) -> Vec<u8> {
-        let mut container = vec![];
 
-        container.extend(chunk_type.bytes());
-        container.extend(data);
-        container
+    /// Computes the CRC over the type and data without concatenating them first.
+    pub fn checksum(chunk_type: &ChunkType, data: &[u8]) -> u32 {
+        let mut digest = CRC.digest();
+        digest.update(&chunk_type.bytes());
+        digest.update(data);
+        digest.finalize()
     }
 
     pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Chunk {
         Chunk {
             length: data.len() as u32,
-            crc: CRC.checksum(Self::get_bytes_for_crc(&chunk_type, &data).as_slice()),
+            crc: Self::checksum(&chunk_type, &data),
             chunk_type,
             data,
         }
     }
 
-    fn length(&self) -> u32 {
+    /// The CRC of an IEND chunk, which never carries data.
+    pub const IEND_CRC: u32 = 0xAE42_6082;
+
+    /// The canonical, empty IEND chunk.
+    pub fn iend() -> Chunk {
+        Chunk {
+            length: 0,
+            chunk_type: ChunkType::IEND,
+            data: vec![],
+            crc: Self::IEND_CRC,
+        }
+    }
+
+    pub fn length(&self) -> u32 {
         self.length
     }
 
+    /// The payload size in bytes, as a `usize` for indexing.
+    pub fn data_len(&self) -> usize {
+        self.data.len()
+    }
+
     pub fn chunk_type(&self) -> &ChunkType {
         &self.chunk_type
     }
 
-    fn data(&self) -> &[u8] {
-        &self.data.as_slice()
+    pub fn data(&self) -> &[u8] {
+        self.data.as_slice()
     }
 
-    fn crc(&self) -> u32 {
+    pub fn crc(&self) -> u32 {
         self.crc
     }
 
     pub fn data_as_string(&self) -> Result<String> {
-        Ok(String::from_utf8(self.data.clone())?)
+        Ok(core::str::from_utf8(&self.data).map(str::to_owned)?)
+    }
+
+    pub fn data_as_bytes(&self) -> &[u8] {
+        &self.data
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
-        let mut container = vec![];
+        let mut container = Vec::with_capacity(12 + self.data.len());
+        self.extend_bytes(&mut container);
+        container
+    }
+
+    /// Appends the serialized chunk to `container` without an intermediate buffer.
+    pub(crate) fn extend_bytes(&self, container: &mut Vec<u8>) {
         container.extend(self.length.to_be_bytes());
         container.extend(self.chunk_type.bytes());
         container.extend(self.data());
         container.extend(self.crc.to_be_bytes());
-        container
     }
 }
 
@@ -154,6 +270,24 @@ mod tests {
         assert_eq!(chunk.length(), 42);
     }
 
+    #[test]
+    fn test_iend() {
+        let iend = Chunk::iend();
+        assert_eq!(iend.crc(), 0xAE426082);
+        assert_eq!(iend.length(), 0);
+        assert_eq!(
+            iend,
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_chunk_data_len() {
+        let chunk = testing_chunk();
+        assert_eq!(chunk.data_len(), 42);
+        assert_eq!(Chunk::new(chunk.chunk_type().clone(), vec![]).data_len(), 0);
+    }
+
     #[test]
     fn test_chunk_type() {
         let chunk = testing_chunk();
@@ -168,6 +302,15 @@ mod tests {
         assert_eq!(chunk_string, expected_chunk_string);
     }
 
+    #[test]
+    fn test_chunk_bytes() {
+        let chunk = testing_chunk();
+        assert_eq!(
+            chunk.data_as_bytes(),
+            "This is where your secret message will be!".as_bytes()
+        );
+    }
+
     #[test]
     fn test_chunk_crc() {
         let chunk = testing_chunk();
@@ -201,6 +344,12 @@ mod tests {
         assert_eq!(chunk.crc(), 2882656334);
     }
 
+    #[test]
+    fn test_valid_chunk_from_vec() {
+        let chunk = Chunk::try_from(testing_chunk().as_bytes()).unwrap();
+        assert_eq!(chunk, testing_chunk());
+    }
+
     #[test]
     fn test_invalid_chunk_from_bytes() {
         let data_length: u32 = 42;
@@ -222,6 +371,129 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_try_from_exact() {
+        let mut bytes = testing_chunk().as_bytes();
+        assert_eq!(Chunk::try_from_exact(&bytes).unwrap(), testing_chunk());
+
+        bytes.extend_from_slice(b"xyz");
+        assert!(Chunk::try_from(bytes.as_slice()).is_ok());
+        assert_eq!(
+            Chunk::try_from_exact(&bytes).err().unwrap().to_string(),
+            "3 unexpected byte(s) after the chunk"
+        );
+    }
+
+    #[test]
+    fn test_junk_chunk_type_from_bytes() {
+        let mut chunk_data = testing_chunk().as_bytes();
+        chunk_data[4..8].copy_from_slice(&[0xFF, 0x00, b'a', b'b']);
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+        assert!(chunk.is_err());
+    }
+
+    #[test]
+    fn test_truncated_chunk_from_bytes() {
+        let chunk = Chunk::try_from([0u8, 0].as_ref());
+        assert!(chunk.is_err());
+
+        let mut chunk_data = testing_chunk().as_bytes();
+        chunk_data.truncate(20);
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+        assert!(chunk.is_err());
+    }
+
+    #[test]
+    fn test_oversized_length_from_bytes() {
+        let chunk_data: Vec<u8> = u32::MAX
+            .to_be_bytes()
+            .iter()
+            .chain("RuSt".as_bytes())
+            .chain("short".as_bytes())
+            .copied()
+            .collect();
+
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+
+        assert_eq!(
+            chunk.err().unwrap().to_string(),
+            "Chunk length exceeds available bytes"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parse_without_crc_check() {
+        let mut bytes =
+            Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"data".to_vec()).as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(Chunk::try_from(bytes.as_slice()).is_err());
+
+        let options = ParseOptions::default().verify_crc(false);
+        let chunk = Chunk::parse_with_options(&bytes, &options).unwrap();
+        assert_eq!(chunk.data(), b"data");
+        assert_ne!(
+            chunk.crc(),
+            Chunk::checksum(chunk.chunk_type(), chunk.data())
+        );
+        let chunk = Chunk::from_reader_with_options(&mut bytes.as_slice(), &options).unwrap();
+        assert_eq!(chunk.as_bytes(), bytes);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_oversized_length_from_reader() {
+        let chunk_data: Vec<u8> = u32::MAX
+            .to_be_bytes()
+            .iter()
+            .chain("RuSt".as_bytes())
+            .chain("short".as_bytes())
+            .copied()
+            .collect();
+
+        let chunk = Chunk::from_reader(&mut chunk_data.as_slice());
+
+        assert_eq!(
+            chunk.err().unwrap().to_string(),
+            "Chunk length exceeds available bytes"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_chunk_from_reader() {
+        let bytes = testing_chunk().as_bytes();
+        let mut reader = bytes.as_slice();
+
+        let chunk = Chunk::from_reader(&mut reader).unwrap();
+        assert_eq!(chunk.chunk_type().to_string(), String::from("RuSt"));
+        assert_eq!(chunk.crc(), 2882656334);
+
+        let end = Chunk::from_reader(&mut reader).err().unwrap();
+        assert!(Chunk::is_end_of_stream(&end));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_truncated_chunk_from_reader() {
+        let bytes = testing_chunk().as_bytes();
+        let mut reader = &bytes[..bytes.len() - 1];
+
+        let error = Chunk::from_reader(&mut reader).err().unwrap();
+        assert!(!Chunk::is_end_of_stream(&error));
+    }
+
+    #[test]
+    fn test_chunk_clone_and_eq() {
+        let chunk = testing_chunk();
+        let cloned = chunk.clone();
+        assert_eq!(chunk, cloned);
+
+        let other = Chunk::new(ChunkType::from_str("RuSt").unwrap(), b"Different".to_vec());
+        assert_ne!(chunk, other);
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
@@ -242,4 +514,10 @@ mod tests {
 
         let _chunk_string = format!("{}", chunk);
     }
+
+    #[test]
+    fn test_chunk_display_non_utf8() {
+        let chunk = Chunk::new(ChunkType::from_str("RuSt").unwrap(), vec![0xFF, 0xFE]);
+        let _chunk_string = format!("{}", chunk);
+    }
 }
@@ -1,67 +1,103 @@
-use std::fmt::Display;
-use std::str::FromStr;
+use alloc::string::String;
+use core::fmt::Display;
+use core::str::FromStr;
 
-use crate::Error;
+use crate::PngError;
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
 pub struct ChunkType {
     bytes: [u8; 4],
 }
 
 impl TryFrom<[u8; 4]> for ChunkType {
-    type Error = Error;
+    type Error = PngError;
 
-    fn try_from(value: [u8; 4]) -> Result<Self, Self::Error> {
-        Ok(ChunkType { bytes: value })
+    fn try_from(bytes: [u8; 4]) -> Result<Self, Self::Error> {
+        if bytes.iter().all(u8::is_ascii_alphabetic) {
+            Ok(ChunkType { bytes })
+        } else {
+            Err(PngError::InvalidChunkType)
+        }
     }
 }
 
 impl FromStr for ChunkType {
-    type Err = Error;
+    type Err = PngError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.chars().any(|val| val.is_numeric()) {
-            Err(Error::from("Numeric values not allowed"))
-        } else {
-            Ok(ChunkType {
-                bytes: s.as_bytes().try_into().unwrap(),
-            })
-        }
+        let bytes: [u8; 4] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| PngError::InvalidChunkType)?;
+        ChunkType::try_from(bytes)
     }
 }
 
 impl Display for ChunkType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            String::from_utf8(self.bytes.try_into().unwrap()).unwrap()
-        )
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.bytes))
     }
 }
 
-#[allow(dead_code)]
 impl ChunkType {
+    /// Chunk types defined by the PNG specification and its common extensions.
+    pub const STANDARD_TYPES: [&'static [u8; 4]; 25] = [
+        b"IHDR", b"PLTE", b"IDAT", b"IEND", b"cHRM", b"cICP", b"gAMA", b"iCCP", b"mDCV", b"cLLI",
+        b"sBIT", b"sRGB", b"bKGD", b"hIST", b"tRNS", b"eXIf", b"pHYs", b"sPLT", b"tIME", b"iTXt",
+        b"tEXt", b"zTXt", b"acTL", b"fcTL", b"fdAT",
+    ];
+
+    pub const IHDR: ChunkType = ChunkType::from_ascii(b"IHDR");
+    pub const IEND: ChunkType = ChunkType::from_ascii(b"IEND");
+
+    /// Builds a chunk type in const context. Unlike [`ChunkType::try_from`],
+    /// the bytes are only checked in debug builds, so use this for known types.
+    pub const fn from_ascii(bytes: &[u8; 4]) -> ChunkType {
+        let mut i = 0;
+        while i < bytes.len() {
+            debug_assert!(
+                bytes[i].is_ascii_alphabetic(),
+                "chunk type must be ASCII letters"
+            );
+            i += 1;
+        }
+        ChunkType { bytes: *bytes }
+    }
+
     pub fn bytes(&self) -> [u8; 4] {
         self.bytes
     }
 
-    fn is_valid(&self) -> bool {
+    pub fn is_standard(&self) -> bool {
+        Self::STANDARD_TYPES.contains(&&self.bytes)
+    }
+
+    pub fn is_valid(&self) -> bool {
         self.is_reserved_bit_valid() && self.bytes().into_iter().all(|c| c.is_ascii())
     }
 
-    fn is_critical(&self) -> bool {
+    /// The property bit (bit 5) of byte `index`, set when that letter is lowercase.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not in `0..4`.
+    pub fn property_bit(&self, index: usize) -> bool {
+        self.bytes[index] & 0x20 != 0
+    }
+
+    pub fn is_critical(&self) -> bool {
         self.bytes[0].is_ascii_uppercase()
     }
 
-    fn is_public(&self) -> bool {
+    pub fn is_public(&self) -> bool {
         self.bytes[1].is_ascii_uppercase()
     }
 
-    fn is_reserved_bit_valid(&self) -> bool {
+    pub fn is_reserved_bit_valid(&self) -> bool {
         self.bytes[2].is_ascii_uppercase()
     }
-    fn is_safe_to_copy(&self) -> bool {
+
+    pub fn is_safe_to_copy(&self) -> bool {
         self.bytes[3].is_ascii_lowercase()
     }
 }
@@ -80,6 +116,26 @@ mod tests {
         assert_eq!(expected, actual.bytes());
     }
 
+    #[test]
+    pub fn test_chunk_type_from_non_letter_bytes() {
+        assert!(ChunkType::try_from([0xFF, 0x00, b'a', b'b']).is_err());
+        assert!(ChunkType::try_from([b'R', b'u', b'1', b't']).is_err());
+    }
+
+    #[test]
+    pub fn test_chunk_type_from_ascii() {
+        const RUST: ChunkType = ChunkType::from_ascii(b"RuSt");
+        assert_eq!(RUST, ChunkType::from_str("RuSt").unwrap());
+        assert_eq!(ChunkType::IEND.to_string(), "IEND");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    pub fn test_chunk_type_from_ascii_non_letter() {
+        ChunkType::from_ascii(b"Ru1t");
+    }
+
     #[test]
     pub fn test_chunk_type_from_str() {
         let expected = ChunkType::try_from([82, 117, 83, 116]).unwrap();
@@ -87,6 +143,13 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    pub fn test_chunk_type_property_bits() {
+        let chunk = ChunkType::from_str("RuSt").unwrap();
+        let bits: Vec<bool> = (0..4).map(|i| chunk.property_bit(i)).collect();
+        assert_eq!(bits, [false, true, false, true]);
+    }
+
     #[test]
     pub fn test_chunk_type_is_critical() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -150,6 +213,21 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    pub fn test_chunk_type_from_str_wrong_length() {
+        assert!(ChunkType::from_str("Rus").is_err());
+        assert!(ChunkType::from_str("RuStt").is_err());
+        assert!(ChunkType::from_str("Ru-t").is_err());
+        assert!(ChunkType::from_str("Ruşt").is_err());
+    }
+
+    #[test]
+    pub fn test_chunk_type_is_standard() {
+        assert!(ChunkType::from_str("IDAT").unwrap().is_standard());
+        assert!(ChunkType::from_str("tEXt").unwrap().is_standard());
+        assert!(!ChunkType::from_str("RuSt").unwrap().is_standard());
+    }
+
     #[test]
     pub fn test_chunk_type_string() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -163,4 +241,14 @@ mod tests {
         let _chunk_string = format!("{}", chunk_type_1);
         let _are_chunks_equal = chunk_type_1 == chunk_type_2;
     }
+
+    #[test]
+    pub fn test_chunk_type_as_map_key() {
+        let mut counts = std::collections::HashMap::new();
+        for s in ["RuSt", "IDAT", "RuSt"] {
+            *counts.entry(ChunkType::from_str(s).unwrap()).or_insert(0) += 1;
+        }
+        assert_eq!(counts[&ChunkType::from_str("RuSt").unwrap()], 2);
+        assert_eq!(counts[&ChunkType::from_str("IDAT").unwrap()], 1);
+    }
 }
@@ -1,70 +1,1257 @@
-use crate::chunk::Chunk;
-use crate::chunk_type::ChunkType;
-use crate::png::Png;
+use crate::args::{Args, DecodeArgs, EncodeArgs, Format, PrintArgs, RemoveArgs};
+use base64::prelude::*;
+use clap::CommandFactory;
+use indicatif::ProgressBar;
+use owo_colors::OwoColorize;
+use pngme::apng;
+use pngme::chunk::Chunk;
+use pngme::chunk_type::ChunkType;
+use pngme::ihdr;
+use pngme::parse::ParseOptions;
+use pngme::payload;
+use pngme::png::Png;
+use pngme::text;
+use pngme::validate::{self, ValidationError};
+use pngme::PngError;
+use regex::Regex;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-fn get_bytes_from_path(path: &str) -> Vec<u8> {
-    let mut f = File::open(path).expect("Cannot open file");
-    let mut buffer = Vec::new();
-    f.read_to_end(&mut buffer)
-        .expect("Unable to read into file buffer");
-    buffer
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Failures detected by the commands themselves rather than the library.
+#[derive(Debug)]
+pub enum CommandError {
+    NoMatchingChunks,
+    CorruptChunks(usize),
+    InvalidChunkType(String),
+    Invalid(Vec<ValidationError>),
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::NoMatchingChunks => write!(f, "no matching chunks found"),
+            CommandError::CorruptChunks(count) => write!(f, "{} corrupt chunk(s) found", count),
+            CommandError::InvalidChunkType(value) => write!(
+                f,
+                "invalid chunk type '{}', expected four ASCII letters",
+                value
+            ),
+            CommandError::Invalid(problems) => write!(f, "{} problem(s) found", problems.len()),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// An error prefixed with the path it concerns, keeping the original as its source.
+#[derive(Debug)]
+pub struct PathError {
+    path: String,
+    source: Error,
+}
+
+impl PathError {
+    pub fn new(path: &str, source: Error) -> PathError {
+        PathError {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    /// The error this one wraps.
+    pub fn inner(&self) -> &Error {
+        &self.source
+    }
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.source)
+    }
+}
+
+impl std::error::Error for PathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Parses a chunk type given on the command line.
+fn parse_chunk_type(value: &str) -> Result<ChunkType> {
+    ChunkType::from_str(value)
+        .map_err(|_| Error::from(CommandError::InvalidChunkType(value.to_string())))
+}
+
+/// Strict parsing, optionally keeping chunks whose CRC doesn't match.
+fn parse_options(no_crc_check: bool) -> ParseOptions {
+    ParseOptions::default().verify_crc(!no_crc_check)
+}
+
+/// Opens the file at `path`, or standard input when `path` is `-`.
+fn open_input(path: &str) -> Result<Box<dyn Read>> {
+    if path == "-" {
+        Ok(Box::new(std::io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+fn read_png(path: &str) -> Result<Png> {
+    Ok(Png::from_reader(open_input(path)?)?)
+}
+
+fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Like [`read_png`], but also downloads `path` when it is an HTTP(S) URL, and
+/// parses according to `options`. Only read-only commands use this, so edits
+/// are never attempted on remote or corrupt files.
+fn read_png_or_url(path: &str, options: &ParseOptions) -> Result<Png> {
+    let reader: Box<dyn Read> = if is_url(path) {
+        Box::new(ureq::get(path).call()?.into_body().into_reader())
+    } else {
+        open_input(path)?
+    };
+    Ok(Png::from_reader_with_options(reader, options)?)
+}
+
+fn is_stdout(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Writes the PNG to `path`, or standard output when `path` is `-`.
+fn write_png(png: &Png, path: &Path) -> Result<()> {
+    if is_stdout(path) {
+        std::io::stdout().write_all(&png.as_bytes())?;
+    } else {
+        write_atomic(path, &png.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes `bytes` to a sibling temp file and renames it over `path`, so an
+/// interrupted write never leaves a truncated file behind. Symlinks are
+/// followed and an existing file keeps its permissions.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            std::fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        // No ErrorKind::CrossesDevices copy fallback: the temp file sits next
+        // to the resolved target, so the rename never leaves its filesystem.
+        std::fs::rename(&temp_path, &path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Copies `path` to `<path>.bak` when it is about to be overwritten in place.
+fn backup(path: &str, write_path: &Path) -> Result<()> {
+    if path == "-" || write_path != Path::new(path) {
+        return Ok(());
+    }
+    let mut backup_path = write_path.as_os_str().to_os_string();
+    backup_path.push(".bak");
+    std::fs::copy(path, backup_path)?;
+    Ok(())
+}
+
+fn report_dry_run(png: &Png, write_path: &Path, change: &str) {
+    println!(
+        "Dry run: would {} and write {} bytes to {}",
+        change,
+        png.byte_size(),
+        write_path.display()
+    );
+}
+
+/// Prints a status line, keeping it off stdout when the PNG itself is written there.
+fn status(write_path: &Path, message: &str, quiet: bool) {
+    if quiet {
+        return;
+    }
+    if is_stdout(write_path) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
 }
 
-pub fn print(path: &str) {
-    let buffer = get_bytes_from_path(path);
-    let png = Png::try_from(buffer.as_slice()).unwrap();
+#[derive(Serialize)]
+struct ChunkInfo {
+    #[serde(rename = "type")]
+    chunk_type: String,
+    offset: usize,
+    length: u32,
+    crc: u32,
+    is_critical: bool,
+}
+
+impl ChunkInfo {
+    fn new(chunk: &Chunk, offset: usize) -> Self {
+        ChunkInfo {
+            chunk_type: chunk.chunk_type().to_string(),
+            offset,
+            length: chunk.length(),
+            crc: chunk.crc(),
+            is_critical: chunk.chunk_type().is_critical(),
+        }
+    }
+}
 
-    let chunk_types: Vec<String> = png
+pub fn print(args: &PrintArgs) -> Result<()> {
+    let png = read_png_or_url(&args.path, &parse_options(args.no_crc_check))?;
+    print_chunks(&png, args)
+}
+
+fn print_chunks(png: &Png, args: &PrintArgs) -> Result<()> {
+    let rows: Vec<(&Chunk, usize)> = png
         .chunks()
         .iter()
-        .map(|c| c.chunk_type().to_string())
+        .zip(png.chunk_offsets())
+        .filter(|(chunk, _)| !args.custom_only || !chunk.chunk_type().is_standard())
         .collect();
-    println!("The following chunks can be decoded:");
-    for chunk in chunk_types {
-        println!("{}", chunk);
+
+    if args.stats {
+        let chunks: Vec<&Chunk> = rows.iter().map(|&(chunk, _)| chunk).collect();
+        print!("{}", chunk_stats(&chunks));
+        return Ok(());
     }
+
+    if let Format::Json = args.format {
+        let infos: Vec<ChunkInfo> = rows
+            .iter()
+            .map(|&(chunk, offset)| ChunkInfo::new(chunk, offset))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&infos)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:<6} {:>10} {:>10} {:<10} {:<10} {:<8} COPY",
+        "TYPE", "OFFSET", "LENGTH", "CRC", "KIND", "SCOPE"
+    );
+    let color = !args.no_color && std::io::stdout().is_terminal();
+    for (chunk, offset) in rows {
+        let chunk_type = chunk.chunk_type();
+        let row = format!(
+            "{:<6} {:>10} {:>10} {:<10} {:<10} {:<8} {}",
+            chunk_type.to_string(),
+            offset,
+            chunk.length(),
+            format!("{:08x}", chunk.crc()),
+            if chunk_type.is_critical() {
+                "critical"
+            } else {
+                "ancillary"
+            },
+            if chunk_type.is_public() {
+                "public"
+            } else {
+                "private"
+            },
+            if chunk_type.is_safe_to_copy() {
+                "safe"
+            } else {
+                "unsafe"
+            },
+        );
+        if !color {
+            println!("{}", row);
+        } else if chunk_type.is_critical() {
+            println!("{}", row.blue());
+        } else {
+            println!("{}", row.yellow());
+        }
+    }
+    if args.apng {
+        print_animation(png)?;
+    }
+    Ok(())
+}
+
+/// Prints the decoded acTL and fcTL chunks of an animated PNG.
+fn print_animation(png: &Png) -> Result<()> {
+    let Some(actl) = png.chunk_by_type("acTL") else {
+        println!("\nNot an animated PNG");
+        return Ok(());
+    };
+    let animation = apng::parse_actl(actl.data())?;
+    println!(
+        "\nAnimation: {} frame(s), {}",
+        animation.num_frames,
+        match animation.num_plays {
+            0 => "loops forever".to_string(),
+            plays => format!("plays {} time(s)", plays),
+        }
+    );
+    for fctl in png.chunks_by_type("fcTL") {
+        let frame = apng::parse_fctl(fctl.data())?;
+        println!(
+            "  Frame {}: {}x{} at ({}, {}), delay {:.3}s, dispose {}, blend {}",
+            frame.sequence_number,
+            frame.width,
+            frame.height,
+            frame.x_offset,
+            frame.y_offset,
+            frame.delay_seconds(),
+            frame.dispose_op_name(),
+            frame.blend_op_name()
+        );
+    }
+    println!("  {} fdAT chunk(s)", png.chunks_by_type("fdAT").len());
+    Ok(())
 }
 
-pub fn encode(path: &str, chunk_type: &str, message: &str) {
-    let buffer = get_bytes_from_path(path);
-    let mut png = Png::try_from(buffer.as_slice()).unwrap();
+pub fn encode(args: &EncodeArgs, quiet: bool) -> Result<()> {
+    if args.path == "-" && matches!(args.positional()?, Some((_, Some("-")))) {
+        return Err(Error::from(
+            "cannot read both the PNG and the message from standard input",
+        ));
+    }
+    let options = ParseOptions::default().require_iend(false);
+    let mut png = Png::from_reader_with_options(open_input(&args.path)?, &options)?;
+    let write_path = args
+        .output
+        .as_deref()
+        .unwrap_or_else(|| Path::new(&args.path));
+
+    let messages = args.messages()?;
+    for (offset, (chunk_type, message)) in messages.iter().enumerate() {
+        let chunk_type = parse_chunk_type(chunk_type)?;
+        // tEXt may repeat, and --as-text keeps it well-formed.
+        let as_text = args.as_text && chunk_type.bytes() == *b"tEXt";
+        if args.as_text && !as_text {
+            return Err(Error::from("--as-text only applies to tEXt chunks"));
+        }
+        if !args.force && !as_text && chunk_type.is_standard() {
+            return Err(Error::from(format!(
+                "{} is a standard PNG chunk type and may corrupt the image, pass --force to use it anyway",
+                chunk_type
+            )));
+        }
+        if chunk_type.is_critical() && !quiet {
+            eprintln!(
+                "Warning: {} is a critical chunk type, image viewers may refuse to open the file",
+                chunk_type
+            );
+        }
+
+        if !args.force && !as_text && png.chunk_by_type(&chunk_type.to_string()).is_some() {
+            return Err(Error::from(format!(
+                "a chunk of type '{}' already exists, pass --force to add another",
+                chunk_type
+            )));
+        }
+
+        let data = match &args.keyword {
+            Some(keyword) if as_text => text::encode_text(keyword, message)?,
+            _ => payload::encode(message, args.compress, args.password.as_deref())?,
+        };
+        let chunk = Chunk::new(chunk_type, data);
+        if args.verbose {
+            status(write_path, &chunk_layout(&chunk), false);
+        }
+        match args.at {
+            Some(index) => png.insert_chunk(index + offset, chunk)?,
+            None => png.append_chunk(chunk),
+        }
+    }
+
+    if png.remove_all_chunks("IEND") == 0 && !quiet {
+        eprintln!("Warning: no IEND chunk found, adding one");
+    }
+    png.append_chunk(Chunk::iend());
 
-    let end = png
-        .remove_chunk("IEND")
-        .expect("Unable to remove end chunk");
+    if args.dry_run {
+        report_dry_run(
+            &png,
+            write_path,
+            &format!("add {} chunk(s)", messages.len()),
+        );
+        return Ok(());
+    }
+    if args.backup {
+        backup(&args.path, write_path)?;
+    }
+    write_png(&png, write_path)?;
+    status(write_path, "Message Encoded!", quiet);
+    Ok(())
+}
 
-    png.append_chunk(Chunk::new(
-        ChunkType::from_str(chunk_type).unwrap(),
-        message.as_bytes().into(),
-    ));
-    png.append_chunk(end);
+/// Summarizes `chunks`: the total count, count and data bytes per type, and the largest chunk.
+fn chunk_stats(chunks: &[&Chunk]) -> String {
+    let mut by_type: HashMap<String, (usize, usize)> = HashMap::new();
+    for chunk in chunks {
+        let entry = by_type.entry(chunk.chunk_type().to_string()).or_default();
+        entry.0 += 1;
+        entry.1 += chunk.data_len();
+    }
+    let mut by_type: Vec<_> = by_type.into_iter().collect();
+    by_type.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut stats = format!("{} chunk(s)\n", chunks.len());
+    for (chunk_type, (count, bytes)) in by_type {
+        stats.push_str(&format!(
+            "  {}: {} chunk(s), {} bytes\n",
+            chunk_type, count, bytes
+        ));
+    }
+    if let Some((index, largest)) = chunks
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, chunk)| (chunk.data_len(), std::cmp::Reverse(*index)))
+    {
+        stats.push_str(&format!(
+            "Largest: {} (chunk {}), {} bytes\n",
+            largest.chunk_type(),
+            index,
+            largest.data_len()
+        ));
+    }
+    stats
+}
 
-    let write_path = std::path::Path::new(path);
-    std::fs::write(write_path, png.as_bytes()).expect("Unable to write to file");
-    println!("Message Encoded!");
+/// Describes the length, type, data and CRC fields that make up `chunk`.
+fn chunk_layout(chunk: &Chunk) -> String {
+    let hex = |bytes: [u8; 4]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    format!(
+        "New {} chunk:\n  length  {}  ({})\n  type    {}  ({})\n  data    {} bytes\n  crc     {}  ({:08x})",
+        chunk.chunk_type(),
+        hex(chunk.length().to_be_bytes()),
+        chunk.length(),
+        hex(chunk.chunk_type().bytes()),
+        chunk.chunk_type(),
+        chunk.data_len(),
+        hex(chunk.crc().to_be_bytes()),
+        chunk.crc()
+    )
 }
 
-pub fn decode(path: &str, chunk_type: &str) {
-    let buffer = get_bytes_from_path(path);
-    let png = Png::try_from(buffer.as_slice()).unwrap();
+/// Formats `data` like `hexdump -C`: offset, 16 hex bytes, then printable ASCII.
+fn hex_dump(data: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, bytes) in data.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (i, byte) in bytes.iter().enumerate() {
+            if i == 8 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x} ", byte));
+        }
+        let ascii: String = bytes
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        dump.push_str(&format!("{:08x}  {:<49} |{}|\n", line * 16, hex, ascii));
+    }
+    dump
+}
 
-    let target = png
+/// The keyword and text of a `tEXt` chunk, if `chunk` is one.
+fn text_fields<'a>(chunk: &Chunk, data: &'a [u8]) -> Option<(Cow<'a, str>, Cow<'a, str>)> {
+    if chunk.chunk_type().bytes() != *b"tEXt" {
+        return None;
+    }
+    let (keyword, text) = text::decode_text(data)?;
+    Some((
+        String::from_utf8_lossy(keyword),
+        String::from_utf8_lossy(text),
+    ))
+}
+
+fn chunk_not_found(chunk_type: &str) -> Error {
+    Error::from(PngError::ChunkNotFound(chunk_type.to_string()))
+}
+
+pub fn decode(args: &DecodeArgs) -> Result<()> {
+    let chunk_type = args.chunk_type()?;
+    let png = read_png_or_url(&args.path, &parse_options(args.no_crc_check))?;
+    decode_chunks(&png, &chunk_type, args)
+}
+
+fn decode_chunks(png: &Png, chunk_type: &str, args: &DecodeArgs) -> Result<()> {
+    // Anything that isn't a literal chunk type is treated as a pattern and
+    // decodes every match, labelled with its type.
+    let pattern = match ChunkType::from_str(chunk_type) {
+        Ok(_) => None,
+        Err(_) => Some(Regex::new(&format!("^(?:{})$", chunk_type))?),
+    };
+    let targets: Vec<&Chunk> = match &pattern {
+        Some(pattern) => png
+            .chunks()
+            .iter()
+            .filter(|chunk| pattern.is_match(&chunk.chunk_type().to_string()))
+            .collect(),
+        None if args.all => png.chunks_by_type(chunk_type),
+        None => png.chunk_by_type(chunk_type).into_iter().collect(),
+    };
+    if targets.is_empty() {
+        return Err(chunk_not_found(chunk_type));
+    }
+
+    let all = args.all || pattern.is_some();
+    for (index, target) in targets.iter().enumerate() {
+        let index = match pattern {
+            Some(_) => target.chunk_type().to_string(),
+            None => index.to_string(),
+        };
+        let data = payload::decode(target.data_as_bytes(), args.password.as_deref())?;
+        if args.raw {
+            std::io::stdout().write_all(&data)?;
+        } else if args.hex {
+            if all {
+                println!("{}:", index);
+            }
+            print!("{}", hex_dump(&data));
+        } else if let Some((keyword, text)) = text_fields(target, &data) {
+            if all {
+                println!("{}: {} = {}", index, keyword, text);
+            } else {
+                println!("Keyword: {}", keyword);
+                println!("Text: {}", text);
+            }
+        } else if all {
+            println!("{}: {}", index, String::from_utf8_lossy(&data));
+        } else {
+            println!("Hidden message is: {}", String::from_utf8_lossy(&data));
+        }
+    }
+    Ok(())
+}
+
+pub fn remove(args: &RemoveArgs, quiet: bool) -> Result<()> {
+    let mut png = read_png(&args.path)?;
+
+    let chunk_type = args.chunk_type()?;
+    let chunk_type = chunk_type.as_deref();
+    let removed = match (chunk_type, args.index) {
+        (_, Some(index)) => png.remove_chunk_at(index).map(|_| 1)?,
+        (Some(chunk_type), None) if args.all => png.remove_all_chunks(chunk_type),
+        (Some(chunk_type), None) => png.remove_chunk(chunk_type).map(|_| 1)?,
+        (None, None) => 0,
+    };
+    if removed == 0 {
+        return Err(chunk_not_found(chunk_type.unwrap_or_default()));
+    }
+
+    let write_path = args
+        .output
+        .as_deref()
+        .unwrap_or_else(|| Path::new(&args.path));
+    if args.dry_run {
+        report_dry_run(&png, write_path, &format!("remove {} chunk(s)", removed));
+        return Ok(());
+    }
+    if args.backup {
+        backup(&args.path, write_path)?;
+    }
+    write_png(&png, write_path)?;
+    status(write_path, &format!("{} chunk(s) removed!", removed), quiet);
+    Ok(())
+}
+
+/// The `.png` files directly inside `dir`, sorted by path.
+fn png_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// A progress bar over `len` files, hidden unless stderr is a terminal.
+fn file_progress(len: usize) -> ProgressBar {
+    if std::io::stderr().is_terminal() {
+        ProgressBar::new(len as u64)
+    } else {
+        ProgressBar::hidden()
+    }
+}
+
+/// Removes every chunk of `chunk_type` from each `.png` file in `dir`, in place.
+pub fn batch_remove(dir: &Path, chunk_type: &str, quiet: bool) -> Result<()> {
+    let paths = png_files(dir)?;
+    let progress = file_progress(paths.len());
+    let mut modified = 0;
+    let mut failed = 0;
+    for path in &paths {
+        let result = (|| -> Result<usize> {
+            let mut png = Png::from_reader(BufReader::new(File::open(path)?))?;
+            let removed = png.remove_all_chunks(chunk_type);
+            if removed > 0 {
+                write_png(&png, path)?;
+            }
+            Ok(removed)
+        })();
+        match result {
+            Ok(0) => {}
+            Ok(removed) => {
+                if !quiet {
+                    progress
+                        .suspend(|| println!("{}: {} chunk(s) removed", path.display(), removed));
+                }
+                modified += 1;
+            }
+            Err(e) => {
+                progress.suspend(|| eprintln!("{}: {}", path.display(), e));
+                failed += 1;
+            }
+        }
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+
+    if !quiet {
+        println!("{} of {} file(s) modified", modified, paths.len());
+    }
+    if failed > 0 {
+        return Err(Error::from(format!(
+            "{} file(s) could not be processed",
+            failed
+        )));
+    }
+    Ok(())
+}
+
+/// Tallies chunk types across every PNG in `dir`, skipping files that don't parse.
+pub fn survey(dir: &Path) -> Result<()> {
+    let paths = png_files(dir)?;
+    let progress = file_progress(paths.len());
+
+    // Chunk count and number of files containing the type.
+    let mut tally: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut failed = Vec::new();
+    for path in &paths {
+        match File::open(path)
+            .map_err(PngError::from)
+            .and_then(|file| Png::from_reader(BufReader::new(file)))
+        {
+            Ok(png) => {
+                let mut seen = HashSet::new();
+                for chunk in &png {
+                    let chunk_type = chunk.chunk_type().to_string();
+                    let entry = tally.entry(chunk_type.clone()).or_default();
+                    entry.0 += 1;
+                    if seen.insert(chunk_type) {
+                        entry.1 += 1;
+                    }
+                }
+            }
+            Err(e) => failed.push((path, e)),
+        }
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+
+    let mut rows: Vec<_> = tally.into_iter().collect();
+    // Stable, so equal counts stay in type order.
+    rows.sort_by_key(|(_, (chunks, _))| std::cmp::Reverse(*chunks));
+    println!("{:<6} {:>10} {:>10}", "TYPE", "CHUNKS", "FILES");
+    for (chunk_type, (chunks, files)) in rows {
+        println!("{:<6} {:>10} {:>10}", chunk_type, chunks, files);
+    }
+    println!(
+        "{} of {} file(s) surveyed",
+        paths.len() - failed.len(),
+        paths.len()
+    );
+    if !failed.is_empty() {
+        eprintln!("{} file(s) could not be parsed:", failed.len());
+        for (path, e) in failed {
+            eprintln!("  {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+pub fn replace(
+    path: &str,
+    chunk_type: &str,
+    message: &str,
+    output: Option<&Path>,
+    quiet: bool,
+) -> Result<()> {
+    let mut png = read_png(path)?;
+
+    png.replace_chunk(chunk_type, message.as_bytes().into())?;
+
+    let write_path = output.unwrap_or_else(|| Path::new(path));
+    write_png(&png, write_path)?;
+    status(write_path, "Chunk replaced!", quiet);
+    Ok(())
+}
+
+pub fn rename(
+    path: &str,
+    chunk_type: &str,
+    new_type: &str,
+    output: Option<&Path>,
+    quiet: bool,
+) -> Result<()> {
+    let mut png = read_png(path)?;
+
+    png.rename_chunk(chunk_type, parse_chunk_type(new_type)?)?;
+
+    let write_path = output.unwrap_or_else(|| Path::new(path));
+    write_png(&png, write_path)?;
+    status(write_path, "Chunk renamed!", quiet);
+    Ok(())
+}
+
+/// Appends a copy of the first `chunk_type` chunk in `src` to `dst`.
+pub fn copy(src: &str, dst: &str, chunk_type: &str, quiet: bool) -> Result<()> {
+    let source = read_png(src)?;
+    let chunk = source
+        .chunk_by_type(chunk_type)
+        .ok_or_else(|| chunk_not_found(chunk_type))?;
+
+    let mut png = read_png(dst).map_err(|e| PathError::new(dst, e))?;
+    png.append_chunk(chunk.clone());
+
+    let write_path = Path::new(dst);
+    write_png(&png, write_path)?;
+    status(write_path, "Chunk copied!", quiet);
+    Ok(())
+}
+
+/// Writes `base` with every non-standard chunk of `other` appended before IEND.
+pub fn merge(base: &str, other: &str, output: &Path, quiet: bool) -> Result<()> {
+    let mut png = read_png(base)?;
+    let other = read_png(other).map_err(|e| PathError::new(other, e))?;
+
+    let mut merged = 0;
+    for chunk in other
+        .chunks()
+        .iter()
+        .filter(|chunk| !chunk.chunk_type().is_standard())
+    {
+        png.append_chunk(chunk.clone());
+        merged += 1;
+    }
+
+    write_png(&png, output)?;
+    status(output, &format!("{} chunk(s) merged", merged), quiet);
+    Ok(())
+}
+
+pub fn to_base64(path: &str) -> Result<()> {
+    let png = read_png(path)?;
+    println!("{}", BASE64_STANDARD.encode(png.as_bytes()));
+    Ok(())
+}
+
+pub fn from_base64(input: &str, output: &Path, quiet: bool) -> Result<()> {
+    let mut text = Vec::new();
+    open_input(input)?.read_to_end(&mut text)?;
+    text.retain(|b| !b.is_ascii_whitespace());
+
+    let png = Png::try_from(BASE64_STANDARD.decode(text)?)?;
+    write_png(&png, output)?;
+    status(output, "PNG decoded!", quiet);
+    Ok(())
+}
+
+/// Removes every ancillary chunk, keeping only what is needed to render the image.
+pub fn strip(path: &str, output: Option<&Path>, quiet: bool) -> Result<()> {
+    let mut png = read_png(path)?;
+
+    let before = png.byte_size();
+    let removed = png.strip_ancillary_chunks();
+
+    let write_path = output.unwrap_or_else(|| Path::new(path));
+    write_png(&png, write_path)?;
+    status(
+        write_path,
+        &format!(
+            "{} chunk(s) removed, saved {} bytes",
+            removed,
+            before - png.byte_size()
+        ),
+        quiet,
+    );
+    Ok(())
+}
+
+pub fn count(path: &str, chunk_type: Option<&str>) -> Result<()> {
+    let png = read_png(path)?;
+
+    let mut counts: HashMap<&ChunkType, usize> = HashMap::new();
+    for chunk in &png {
+        *counts.entry(chunk.chunk_type()).or_insert(0) += 1;
+    }
+
+    match chunk_type {
+        Some(chunk_type) => {
+            let count = counts.get(&parse_chunk_type(chunk_type)?).unwrap_or(&0);
+            println!("{} chunk(s) of type {}", count, chunk_type);
+        }
+        None => {
+            println!("{} chunk(s)", png.chunk_count());
+            let mut counts: Vec<_> = counts.into_iter().collect();
+            counts.sort_by_key(|(chunk_type, _)| chunk_type.bytes());
+            for (chunk_type, count) in counts {
+                println!("  {}: {}", chunk_type, count);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The decompressed payload of `chunk`, left as is when it is encrypted.
+fn extracted_payload(chunk: &Chunk) -> Result<Vec<u8>> {
+    match payload::decode(chunk.data_as_bytes(), None) {
+        Err(PngError::PasswordRequired) => Ok(chunk.data_as_bytes().to_vec()),
+        result => Ok(result?),
+    }
+}
+
+/// Prints the image size, bit depth, color type and interlacing from IHDR.
+pub fn dimensions(path: &str) -> Result<()> {
+    let png = read_png(path)?;
+    let header = png
+        .chunk_by_type("IHDR")
+        .ok_or_else(|| chunk_not_found("IHDR"))?;
+    let info = ihdr::parse_ihdr(header.data())?;
+
+    println!("Width:       {}", info.width);
+    println!("Height:      {}", info.height);
+    println!("Bit depth:   {}", info.bit_depth);
+    println!(
+        "Color type:  {} ({})",
+        info.color_type,
+        info.color_type_name()
+    );
+    println!(
+        "Interlace:   {}",
+        if info.is_interlaced() {
+            "Adam7"
+        } else {
+            "none"
+        }
+    );
+    Ok(())
+}
+
+/// Compares the chunks of `a` and `b` type by type: `+` for types only in
+/// `b`, `-` for types only in `a`, `~` where the chunks of a type differ.
+pub fn diff(a: &str, b: &str) -> Result<()> {
+    let (a, b) = (read_png(a)?, read_png(b)?);
+
+    let mut types: Vec<&ChunkType> = Vec::new();
+    for chunk in a.chunks().iter().chain(b.chunks()) {
+        if !types.contains(&chunk.chunk_type()) {
+            types.push(chunk.chunk_type());
+        }
+    }
+
+    let mut differences = 0;
+    for chunk_type in types {
+        let chunk_type = chunk_type.to_string();
+        let (in_a, in_b) = (a.chunks_by_type(&chunk_type), b.chunks_by_type(&chunk_type));
+        if in_a.is_empty() {
+            println!("+{}", chunk_type);
+        } else if in_b.is_empty() {
+            println!("-{}", chunk_type);
+        } else if in_a != in_b {
+            println!("~{} (data differs)", chunk_type);
+        } else {
+            continue;
+        }
+        differences += 1;
+    }
+    if differences == 0 {
+        println!("No differences");
+    }
+    Ok(())
+}
+
+pub fn export(path: &str, chunk_type: &str, output: &Path, quiet: bool) -> Result<()> {
+    let png = read_png(path)?;
+    let chunk = png
         .chunk_by_type(chunk_type)
-        .expect("Unable to locate chunk_type");
+        .ok_or_else(|| chunk_not_found(chunk_type))?;
+
+    if is_stdout(output) {
+        std::io::stdout().write_all(chunk.data())?;
+    } else {
+        std::fs::write(output, chunk.data())?;
+    }
+    status(
+        output,
+        &format!("{} bytes exported!", chunk.data_len()),
+        quiet,
+    );
+    Ok(())
+}
+
+/// Writes the payload of every non-standard chunk (or every chunk of
+/// `chunk_type`) to `output`. When `output` is a directory each payload gets
+/// its own file, otherwise the payloads are concatenated one per line.
+pub fn extract_all(path: &str, output: &Path, chunk_type: Option<&str>, quiet: bool) -> Result<()> {
+    let png = read_png(path)?;
+
+    let targets: Vec<(usize, &Chunk)> = png
+        .chunks()
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| match chunk_type {
+            Some(chunk_type) => c.chunk_type().to_string() == chunk_type,
+            None => !c.chunk_type().is_standard(),
+        })
+        .collect();
+    if targets.is_empty() {
+        return Err(Error::from(CommandError::NoMatchingChunks));
+    }
+
+    if output.is_dir() {
+        for (index, chunk) in &targets {
+            let file_name = format!("{}-{}.bin", index, chunk.chunk_type());
+            let data = extracted_payload(chunk)?;
+            std::fs::write(output.join(file_name), data)?;
+        }
+    } else {
+        let mut contents = Vec::new();
+        for (_, chunk) in &targets {
+            contents.extend(extracted_payload(chunk)?);
+            contents.push(b'\n');
+        }
+        std::fs::write(output, contents)?;
+    }
+    if !quiet {
+        println!("{} chunk(s) extracted!", targets.len());
+    }
+    Ok(())
+}
+
+pub fn info(chunk_type: &str) -> Result<()> {
+    let chunk_type = parse_chunk_type(chunk_type)?;
+
+    println!("Chunk type:         {}", chunk_type);
+    println!("Critical:           {}", chunk_type.is_critical());
+    println!("Public:             {}", chunk_type.is_public());
+    println!("Reserved bit valid: {}", chunk_type.is_reserved_bit_valid());
+    println!("Safe to copy:       {}", chunk_type.is_safe_to_copy());
+    println!("Valid:              {}", chunk_type.is_valid());
+    Ok(())
+}
+
+/// Prints what the case of each letter in a chunk type means, by asking
+/// [`ChunkType`] about types that differ only in that letter.
+pub fn spec() -> Result<()> {
+    type Property = (
+        &'static str,
+        fn(&ChunkType) -> bool,
+        &'static str,
+        &'static str,
+    );
+    let properties: [Property; 4] = [
+        (
+            "ancillary bit",
+            ChunkType::is_critical,
+            "critical",
+            "ancillary",
+        ),
+        ("private bit", ChunkType::is_public, "public", "private"),
+        (
+            "reserved bit",
+            ChunkType::is_reserved_bit_valid,
+            "valid",
+            "invalid",
+        ),
+        (
+            "safe-to-copy bit",
+            ChunkType::is_safe_to_copy,
+            "safe to copy",
+            "unsafe to copy",
+        ),
+    ];
+
+    println!("Each chunk type is four ASCII letters. Bit 5 of each letter (its case) is a flag:");
+    for (index, (name, property, when_true, when_false)) in properties.iter().enumerate() {
+        let mut bytes = *b"AAAA";
+        let upper = ChunkType::try_from(bytes)?;
+        bytes[index] = bytes[index].to_ascii_lowercase();
+        let lower = ChunkType::try_from(bytes)?;
+        let label = |chunk_type: &ChunkType| {
+            if property(chunk_type) {
+                *when_true
+            } else {
+                *when_false
+            }
+        };
+        println!(
+            "  letter {}  {:<17} uppercase: {:<15} lowercase: {}",
+            index + 1,
+            name,
+            label(&upper),
+            label(&lower)
+        );
+    }
+    Ok(())
+}
+
+pub fn search(path: &str, query: &str, ignore_case: bool) -> Result<()> {
+    let png = read_png(path)?;
 
-    println!("Hidden message is: {}", target.data_as_string().unwrap());
+    let needle = if ignore_case {
+        query.to_lowercase()
+    } else {
+        query.to_string()
+    };
+    let mut found = 0;
+    for (index, chunk) in png.chunks().iter().enumerate() {
+        let text = String::from_utf8_lossy(chunk.data_as_bytes());
+        let matches = if ignore_case {
+            text.to_lowercase().contains(&needle)
+        } else {
+            text.contains(&needle)
+        };
+        if matches {
+            println!("{}: {}", index, chunk.chunk_type());
+            found += 1;
+        }
+    }
+    if found == 0 {
+        return Err(Error::from(format!("no chunk contains '{}'", query)));
+    }
+    Ok(())
 }
 
-pub fn remove(path: &str, chunk_type: &str) {
-    let buffer = get_bytes_from_path(path);
-    let mut png = Png::try_from(buffer.as_slice()).unwrap();
+/// Prints every structural problem found by [`Png::validate_bytes`] instead of
+/// stopping at the first.
+pub fn validate(path: &str) -> Result<()> {
+    let mut bytes = Vec::new();
+    open_input(path)?.read_to_end(&mut bytes)?;
 
-    png.remove_chunk(chunk_type)
-        .expect("Unable to remove chunk");
-    let write_path = std::path::Path::new(path);
-    std::fs::write(write_path, png.as_bytes()).expect("Unable to write to file");
-    println!("Chunk removed!");
+    let problems = Png::validate_bytes(&bytes);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if !problems.is_empty() {
+        return Err(Error::from(CommandError::Invalid(problems)));
+    }
+    println!("No problems found");
+    Ok(())
+}
+
+const REPL_HELP: &str = "\
+Commands:
+  print           list the chunks
+  decode TYPE     show the message in the first TYPE chunk
+  add TYPE MSG    append a TYPE chunk holding MSG
+  rm TYPE         remove the first TYPE chunk
+  save            write the changes back to the file
+  help            show this list
+  quit            leave, discarding unsaved changes";
+
+/// Runs one REPL `command` with its `args` against the in-memory `png`.
+fn repl_command(
+    png: &mut Png,
+    path: &str,
+    command: &str,
+    args: &str,
+    dirty: &mut bool,
+) -> Result<()> {
+    match command {
+        "" => {}
+        "print" => print_chunks(png, &PrintArgs::default())?,
+        "decode" => decode_chunks(png, args, &DecodeArgs::default())?,
+        "add" => {
+            let (chunk_type, message) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            let chunk_type = parse_chunk_type(chunk_type)?;
+            png.append_chunk(Chunk::new(chunk_type, message.trim_start().into()));
+            *dirty = true;
+            println!("Chunk added");
+        }
+        "rm" => {
+            let index = png
+                .chunks()
+                .iter()
+                .position(|chunk| chunk.chunk_type().to_string() == args)
+                .ok_or_else(|| chunk_not_found(args))?;
+            // Goes through the index guard so IHDR and IEND stay in place.
+            png.remove_chunk_at(index)?;
+            *dirty = true;
+            println!("Chunk removed");
+        }
+        "save" => {
+            write_png(png, Path::new(path))?;
+            *dirty = false;
+            println!("Saved {}", path);
+        }
+        "help" => println!("{}", REPL_HELP),
+        _ => {
+            return Err(Error::from(format!(
+                "unknown command '{}', try help",
+                command
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Loads `path` once and edits it in memory from an interactive prompt. The
+/// file is only written on `save`.
+pub fn repl(path: &str) -> Result<()> {
+    if path == "-" {
+        return Err(Error::from(
+            "repl reads commands from standard input, so the PNG must be a file",
+        ));
+    }
+    let mut png = read_png(path)?;
+    let mut dirty = false;
+    let stdin = std::io::stdin();
+    let mut line = String::new();
+    loop {
+        print!("pngme> ");
+        std::io::stdout().flush()?;
+        line.clear();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            println!();
+            break;
+        }
+
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        if command == "quit" || command == "exit" {
+            break;
+        }
+        if let Err(e) = repl_command(&mut png, path, command, rest.trim_start(), &mut dirty) {
+            eprintln!("Error: {}", e);
+        }
+    }
+    if dirty {
+        eprintln!("Unsaved changes discarded");
+    }
+    Ok(())
+}
+
+pub fn completions(shell: clap_complete::Shell) -> Result<()> {
+    let mut command = Args::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+    Ok(())
+}
+
+/// Recomputes the CRC of every chunk straight from the file bytes, so corrupt
+/// chunks are reported instead of aborting the parse.
+pub fn verify(path: &str) -> Result<()> {
+    let mut bytes = Vec::new();
+    open_input(path)?.read_to_end(&mut bytes)?;
+    if !bytes.starts_with(&Png::STANDARD_HEADER) {
+        return Err(Error::from(PngError::InvalidSignature));
+    }
+
+    // Like `validate_bytes`, stop at IEND so appended data isn't mistaken for chunks.
+    let mut offset = Png::STANDARD_HEADER.len();
+    let mut corrupt = 0;
+    while offset < bytes.len() {
+        let (type_bytes, data, stored) =
+            validate::chunk_fields(&bytes, offset).ok_or(PngError::Truncated)?;
+        let chunk_type = ChunkType::try_from(type_bytes)?;
+        let computed = Chunk::checksum(&chunk_type, data);
+
+        if stored == computed {
+            println!("{:<6} offset {:>8}  ok", chunk_type.to_string(), offset);
+        } else {
+            println!(
+                "{:<6} offset {:>8}  CRC mismatch: stored {:08x}, computed {:08x}",
+                chunk_type.to_string(),
+                offset,
+                stored,
+                computed
+            );
+            corrupt += 1;
+        }
+        offset += 12 + data.len();
+        if &type_bytes == b"IEND" {
+            break;
+        }
+    }
+
+    if corrupt > 0 {
+        return Err(Error::from(CommandError::CorruptChunks(corrupt)));
+    }
+    println!("All chunks OK");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_dump() {
+        let dump = hex_dump(b"This is where your secret message will be!\x00\xff");
+        let expected = "\
+00000000  54 68 69 73 20 69 73 20  77 68 65 72 65 20 79 6f  |This is where yo|
+00000010  75 72 20 73 65 63 72 65  74 20 6d 65 73 73 61 67  |ur secret messag|
+00000020  65 20 77 69 6c 6c 20 62  65 21 00 ff              |e will be!..|
+";
+        assert_eq!(dump, expected);
+    }
+
+    #[test]
+    fn test_chunk_layout() {
+        let chunk = Chunk::new(ChunkType::from_str("veRb").unwrap(), b"hello".to_vec());
+        let layout = chunk_layout(&chunk);
+        assert!(layout.contains("length  00 00 00 05  (5)"));
+        assert!(layout.contains("type    76 65 52 62  (veRb)"));
+        assert!(layout.contains(&format!("({:08x})", chunk.crc())));
+    }
+
+    #[test]
+    fn test_chunk_stats() {
+        let chunk = |chunk_type, data: &str| {
+            Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.into())
+        };
+        let chunks = [
+            chunk("IHDR", "header"),
+            chunk("tEXt", "a"),
+            chunk("tEXt", "long text"),
+        ];
+        let stats = chunk_stats(&chunks.iter().collect::<Vec<_>>());
+        assert_eq!(
+            stats,
+            "3 chunk(s)\n  tEXt: 2 chunk(s), 10 bytes\n  IHDR: 1 chunk(s), 6 bytes\nLargest: tEXt (chunk 2), 9 bytes\n"
+        );
+    }
 }
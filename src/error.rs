@@ -0,0 +1,148 @@
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use std::io;
+
+/// Everything that can go wrong while reading, editing or decoding a PNG.
+#[derive(Debug)]
+pub enum PngError {
+    InvalidSignature,
+    InvalidChunkType,
+    Truncated,
+    LengthExceedsData,
+    TrailingBytes(usize),
+    CrcMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    MissingIhdr,
+    InvalidIhdr(usize),
+    InvalidActl(usize),
+    InvalidFctl(usize),
+    MisplacedIend,
+    DuplicateChunk(String),
+    InvalidKeyword(String),
+    ChunkNotFound(String),
+    IndexOutOfRange {
+        index: usize,
+        min: usize,
+        max: usize,
+    },
+    InvalidUtf8(core::str::Utf8Error),
+    PasswordRequired,
+    NotEncrypted,
+    EncryptedTruncated,
+    WrongPassword,
+    Crypto(String),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl Display for PngError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PngError::InvalidSignature => write!(f, "Invalid PNG signature"),
+            PngError::InvalidChunkType => {
+                write!(f, "Chunk type must be exactly four ASCII letters")
+            }
+            PngError::Truncated => write!(f, "Chunk is truncated"),
+            PngError::LengthExceedsData => write!(f, "Chunk length exceeds available bytes"),
+            PngError::TrailingBytes(count) => {
+                write!(f, "{} unexpected byte(s) after the chunk", count)
+            }
+            PngError::CrcMismatch { expected, actual } => write!(
+                f,
+                "Invalid CRC: expected {:08x}, found {:08x}",
+                expected, actual
+            ),
+            PngError::MissingIhdr => write!(f, "IHDR must be the first chunk"),
+            PngError::InvalidIhdr(len) => {
+                write!(f, "IHDR data must be 13 bytes, found {}", len)
+            }
+            PngError::InvalidActl(len) => {
+                write!(f, "acTL data must be 8 bytes, found {}", len)
+            }
+            PngError::InvalidFctl(len) => {
+                write!(f, "fcTL data must be 26 bytes, found {}", len)
+            }
+            PngError::MisplacedIend => write!(f, "IEND must be the last chunk"),
+            PngError::DuplicateChunk(chunk_type) => write!(f, "duplicate {} chunk", chunk_type),
+            PngError::InvalidKeyword(keyword) => {
+                write!(
+                    f,
+                    "invalid tEXt keyword '{}', expected 1-79 printable characters",
+                    keyword
+                )
+            }
+            PngError::ChunkNotFound(chunk_type) => {
+                write!(f, "no chunk of type '{}' found", chunk_type)
+            }
+            PngError::IndexOutOfRange { index, min, max } => {
+                write!(f, "chunk index {} is out of range {}..={}", index, min, max)
+            }
+            PngError::InvalidUtf8(e) => write!(f, "{}", e),
+            PngError::PasswordRequired => {
+                write!(f, "Message is encrypted, a password is required")
+            }
+            PngError::NotEncrypted => write!(f, "Message is not encrypted"),
+            PngError::EncryptedTruncated => write!(f, "Encrypted message is truncated"),
+            PngError::WrongPassword => write!(f, "Wrong password or corrupted message"),
+            PngError::Crypto(message) => write!(f, "{}", message),
+            #[cfg(feature = "std")]
+            PngError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl core::error::Error for PngError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            PngError::InvalidUtf8(e) => Some(e),
+            #[cfg(feature = "std")]
+            PngError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for PngError {
+    fn from(error: io::Error) -> Self {
+        PngError::Io(error)
+    }
+}
+
+impl From<core::str::Utf8Error> for PngError {
+    fn from(error: core::str::Utf8Error) -> Self {
+        PngError::InvalidUtf8(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            PngError::ChunkNotFound("RuSt".to_string()).to_string(),
+            "no chunk of type 'RuSt' found"
+        );
+        assert_eq!(
+            PngError::CrcMismatch {
+                expected: 0xabcd,
+                actual: 1
+            }
+            .to_string(),
+            "Invalid CRC: expected 0000abcd, found 00000001"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_io_source() {
+        let error = PngError::from(io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(&error, PngError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+}
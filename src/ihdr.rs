@@ -0,0 +1,69 @@
+use crate::{PngError, Result};
+
+/// The fields of an IHDR chunk.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IhdrInfo {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: u8,
+}
+
+impl IhdrInfo {
+    /// The name the PNG spec gives to `color_type`.
+    pub fn color_type_name(&self) -> &'static str {
+        match self.color_type {
+            0 => "grayscale",
+            2 => "truecolor",
+            3 => "indexed",
+            4 => "grayscale with alpha",
+            6 => "truecolor with alpha",
+            _ => "unknown",
+        }
+    }
+
+    pub fn is_interlaced(&self) -> bool {
+        self.interlace_method == 1
+    }
+}
+
+/// Parses the 13 bytes of IHDR chunk data.
+pub fn parse_ihdr(data: &[u8]) -> Result<IhdrInfo> {
+    let data: &[u8; 13] = data
+        .try_into()
+        .map_err(|_| PngError::InvalidIhdr(data.len()))?;
+    Ok(IhdrInfo {
+        width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+        height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+        bit_depth: data[8],
+        color_type: data[9],
+        compression_method: data[10],
+        filter_method: data[11],
+        interlace_method: data[12],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ihdr() {
+        let data = [0, 0, 0, 100, 0, 0, 0, 75, 8, 6, 0, 0, 1];
+        let info = parse_ihdr(&data).unwrap();
+        assert_eq!(info.width, 100);
+        assert_eq!(info.height, 75);
+        assert_eq!(info.bit_depth, 8);
+        assert_eq!(info.color_type_name(), "truecolor with alpha");
+        assert!(info.is_interlaced());
+    }
+
+    #[test]
+    fn test_parse_ihdr_wrong_length() {
+        assert!(parse_ihdr(&[0; 12]).is_err());
+        assert!(parse_ihdr(&[0; 14]).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+//! PNG chunk parsing and editing.
+//!
+//! Without the `std` feature only the chunk codec is built (`chunk`,
+//! `chunk_type`, `ihdr`, `apng`, `text`), using `alloc` for buffers. File
+//! I/O, payload compression and encryption, and validation need `std`; the
+//! binary needs the `cli` feature, and `wasm` adds JavaScript bindings.
+//!
+//! `cli` is the default feature so that installing and testing the binary
+//! needs no flags. That pulls in clap, ureq, regex and the other CLI
+//! dependencies, so library users should opt out:
+//!
+//! ```toml
+//! pngme = { version = "0.1", default-features = false, features = ["std"] }
+//! ```
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod apng;
+pub mod chunk;
+pub mod chunk_type;
+pub mod error;
+pub mod ihdr;
+pub mod parse;
+#[cfg(feature = "std")]
+pub mod payload;
+#[cfg(feature = "std")]
+pub mod png;
+pub mod text;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::PngError;
+
+pub type Result<T> = core::result::Result<T, PngError>;
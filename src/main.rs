@@ -1,36 +1,226 @@
 use crate::args::Args;
-use crate::args::Commands::{Decode, Encode, Print, Remove};
+use crate::args::Commands::{
+    BatchRemove, Completions, Copy, Count, Decode, Diff, Dimensions, Encode, Export, ExtractAll,
+    FromBase64, Info, Merge, Print, Remove, Rename, Repl, Replace, Search, Spec, Strip, Survey,
+    ToBase64, Validate, Verify,
+};
 use crate::commands::print;
 use clap::Parser;
-use commands::{decode, encode, remove};
+use commands::{
+    batch_remove, completions, copy, count, decode, diff, dimensions, encode, export, extract_all,
+    from_base64, info, merge, remove, rename, repl, replace, search, spec, strip, survey,
+    to_base64, validate, verify,
+};
+use commands::{CommandError, Error, PathError};
+use pngme::validate::ValidationError;
+use pngme::PngError;
 
 mod args;
-mod chunk;
-mod chunk_type;
 mod commands;
-mod png;
 
-pub type Error = Box<dyn std::error::Error>;
-pub type Result<T> = std::result::Result<T, Error>;
+/// Exit codes reported for each category of failure, see `pngme --help`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Failure {
+    Other = 1,
+    FileNotFound = 2,
+    InvalidPng = 3,
+    ChunkNotFound = 4,
+    CrcMismatch = 5,
+    Usage = 6,
+}
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+impl From<&Error> for Failure {
+    fn from(error: &Error) -> Self {
+        if let Some(error) = error.downcast_ref::<PathError>() {
+            return Failure::from(error.inner());
+        }
+        let io = match error.downcast_ref::<PngError>() {
+            Some(PngError::Io(io)) => Some(io),
+            Some(
+                PngError::InvalidSignature
+                | PngError::InvalidChunkType
+                | PngError::Truncated
+                | PngError::LengthExceedsData
+                | PngError::MissingIhdr
+                | PngError::InvalidIhdr(_)
+                | PngError::InvalidActl(_)
+                | PngError::InvalidFctl(_)
+                | PngError::MisplacedIend
+                | PngError::DuplicateChunk(_),
+            ) => return Failure::InvalidPng,
+            Some(PngError::CrcMismatch { .. }) => return Failure::CrcMismatch,
+            Some(PngError::ChunkNotFound(_)) => return Failure::ChunkNotFound,
+            Some(_) => return Failure::Other,
+            None => error.downcast_ref::<std::io::Error>(),
+        };
+        if io.is_some_and(|io| io.kind() == std::io::ErrorKind::NotFound) {
+            return Failure::FileNotFound;
+        }
+        match error.downcast_ref::<CommandError>() {
+            Some(CommandError::NoMatchingChunks) => Failure::ChunkNotFound,
+            Some(CommandError::CorruptChunks(_)) => Failure::CrcMismatch,
+            Some(CommandError::InvalidChunkType(_)) => Failure::Usage,
+            // Bad checksums alone match what `verify` reports for the same file.
+            Some(CommandError::Invalid(problems))
+                if problems
+                    .iter()
+                    .all(|problem| matches!(problem, ValidationError::CrcMismatch { .. })) =>
+            {
+                Failure::CrcMismatch
+            }
+            Some(CommandError::Invalid(_)) => Failure::InvalidPng,
+            None => Failure::Other,
+        }
+    }
+}
 
-    match &args.command {
-        Encode {
+fn main() {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            // Help and version requests are printed to stdout and are not failures.
+            let _ = e.print();
+            let code = if e.use_stderr() {
+                Failure::Usage as i32
+            } else {
+                0
+            };
+            std::process::exit(code);
+        }
+    };
+
+    let result = match &args.command {
+        Encode(encode_args) => encode(encode_args, args.quiet),
+        Decode(decode_args) => decode(decode_args),
+        Remove(remove_args) => remove(remove_args, args.quiet),
+        BatchRemove { dir, chunk_type } => batch_remove(dir, chunk_type, args.quiet),
+        Survey { dir } => survey(dir),
+        Print(print_args) => print(print_args),
+        Copy {
+            src,
+            dst,
+            chunk_type,
+        } => copy(src, dst, chunk_type, args.quiet),
+        Count { path, chunk_type } => count(path, chunk_type.as_deref()),
+        Dimensions { path } => dimensions(path),
+        Diff { a, b } => diff(a, b),
+        Export {
+            path,
+            chunk_type,
+            output,
+        } => export(path, chunk_type, output, args.quiet),
+        ExtractAll {
+            path,
+            output,
+            chunk_type,
+        } => extract_all(path, output, chunk_type.as_deref(), args.quiet),
+        Info { chunk_type } => info(chunk_type),
+        Merge {
+            base,
+            other,
+            output,
+        } => merge(base, other, output, args.quiet),
+        Spec => spec(),
+        Repl { path } => repl(path),
+        Replace {
             path,
             chunk_type,
             message,
-        } => {
-            encode(path, chunk_type, message);
-        }
-        Decode { path, chunk_type } => decode(path, chunk_type),
-        Remove { path, chunk_type } => {
-            remove(path, chunk_type);
-        }
-        Print { path } => {
-            print(path);
-        }
+            output,
+        } => replace(path, chunk_type, message, output.as_deref(), args.quiet),
+        Rename {
+            path,
+            chunk_type,
+            new_type,
+            output,
+        } => rename(path, chunk_type, new_type, output.as_deref(), args.quiet),
+        Search {
+            path,
+            query,
+            ignore_case,
+        } => search(path, query, *ignore_case),
+        ToBase64 { path } => to_base64(path),
+        FromBase64 { input, output } => from_base64(input, output, args.quiet),
+        Strip { path, output } => strip(path, output.as_deref(), args.quiet),
+        Validate { path } => validate(path),
+        Verify { path } => verify(path),
+        Completions { shell } => completions(*shell),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(Failure::from(&e) as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_from_error() {
+        let not_found: Error = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        assert_eq!(Failure::from(&not_found), Failure::FileNotFound);
+        let not_found: Error =
+            PngError::from(std::io::Error::from(std::io::ErrorKind::NotFound)).into();
+        assert_eq!(Failure::from(&not_found), Failure::FileNotFound);
+        assert_eq!(
+            Failure::from(&Error::from(PngError::InvalidSignature)),
+            Failure::InvalidPng
+        );
+        assert_eq!(
+            Failure::from(&Error::from(PngError::ChunkNotFound("RuSt".to_string()))),
+            Failure::ChunkNotFound
+        );
+        assert_eq!(
+            Failure::from(&Error::from(PngError::CrcMismatch {
+                expected: 0,
+                actual: 1
+            })),
+            Failure::CrcMismatch
+        );
+        assert_eq!(
+            Failure::from(&Error::from(CommandError::NoMatchingChunks)),
+            Failure::ChunkNotFound
+        );
+        assert_eq!(
+            Failure::from(&Error::from(CommandError::CorruptChunks(2))),
+            Failure::CrcMismatch
+        );
+        assert_eq!(
+            Failure::from(&Error::from(CommandError::InvalidChunkType("ab".into()))),
+            Failure::Usage
+        );
+        let crc = ValidationError::CrcMismatch {
+            index: 1,
+            expected: 0,
+            actual: 1,
+        };
+        assert_eq!(
+            Failure::from(&Error::from(CommandError::Invalid(vec![crc.clone()]))),
+            Failure::CrcMismatch
+        );
+        assert_eq!(
+            Failure::from(&Error::from(CommandError::Invalid(vec![
+                crc,
+                ValidationError::MissingIhdr
+            ]))),
+            Failure::InvalidPng
+        );
+        assert_eq!(Failure::from(&Error::from("boom")), Failure::Other);
+    }
+
+    #[test]
+    fn test_failure_through_path_error() {
+        let wrap = |error: Error| Error::from(PathError::new("a.png", error));
+        assert_eq!(
+            Failure::from(&wrap(PngError::InvalidSignature.into())),
+            Failure::InvalidPng
+        );
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(
+            Failure::from(&wrap(not_found.into())),
+            Failure::FileNotFound
+        );
     }
-    Ok(())
 }
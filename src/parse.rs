@@ -0,0 +1,77 @@
+/// Controls how strictly chunks and PNGs are parsed.
+///
+/// The default is strict, matching `Chunk::try_from` and `Png::try_from`.
+/// Recovery tools can relax individual checks, e.g.
+/// `ParseOptions::default().verify_crc(false)`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParseOptions {
+    /// Reject chunks whose stored CRC doesn't match their type and data. When
+    /// off, the stored CRC is kept as is so corrupt chunks can be inspected.
+    pub verify_crc: bool,
+    /// Reject files that don't start with the standard PNG signature. When off,
+    /// the first 8 bytes are taken as the signature whatever they hold, and are
+    /// written back unchanged.
+    pub require_signature: bool,
+    /// Reject files whose last chunk isn't IEND, for example truncated downloads.
+    pub require_iend: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::strict()
+    }
+}
+
+impl ParseOptions {
+    /// Every check enabled.
+    pub fn strict() -> Self {
+        ParseOptions {
+            verify_crc: true,
+            require_signature: true,
+            require_iend: true,
+        }
+    }
+
+    /// Every check disabled.
+    pub fn lenient() -> Self {
+        ParseOptions {
+            verify_crc: false,
+            require_signature: false,
+            require_iend: false,
+        }
+    }
+
+    pub fn verify_crc(mut self, verify_crc: bool) -> Self {
+        self.verify_crc = verify_crc;
+        self
+    }
+
+    pub fn require_signature(mut self, require_signature: bool) -> Self {
+        self.require_signature = require_signature;
+        self
+    }
+
+    pub fn require_iend(mut self, require_iend: bool) -> Self {
+        self.require_iend = require_iend;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let options = ParseOptions::default()
+            .verify_crc(false)
+            .require_iend(false);
+        assert!(!options.verify_crc);
+        assert!(!options.require_iend);
+        assert!(options.require_signature);
+        assert_eq!(
+            ParseOptions::lenient().require_signature(true),
+            ParseOptions::strict().verify_crc(false).require_iend(false)
+        );
+    }
+}
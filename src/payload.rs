@@ -0,0 +1,247 @@
+use crate::{PngError, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Self-describing 4-byte prefix written by [`encode`]: the magic `PM`, a
+/// format version and a byte of flags saying which transforms were applied.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PayloadHeader {
+    pub compressed: bool,
+    pub encrypted: bool,
+}
+
+impl PayloadHeader {
+    pub const MAGIC: [u8; 2] = *b"PM";
+    pub const VERSION: u8 = 2;
+    pub const LEN: usize = 4;
+
+    const COMPRESSED: u8 = 0b01;
+    const ENCRYPTED: u8 = 0b10;
+
+    pub fn to_bytes(self) -> [u8; 4] {
+        let mut flags = 0;
+        if self.compressed {
+            flags |= Self::COMPRESSED;
+        }
+        if self.encrypted {
+            flags |= Self::ENCRYPTED;
+        }
+        [Self::MAGIC[0], Self::MAGIC[1], Self::VERSION, flags]
+    }
+
+    /// Reads the header at the start of `data`, or `None` if there isn't one.
+    /// [`encode`] never writes a header without flags, so one is not accepted.
+    pub fn from_bytes(data: &[u8]) -> Option<PayloadHeader> {
+        let [m0, m1, version, flags] = *data.first_chunk::<4>()?;
+        let known = Self::COMPRESSED | Self::ENCRYPTED;
+        if [m0, m1] != Self::MAGIC || version != Self::VERSION || flags == 0 || flags & !known != 0
+        {
+            return None;
+        }
+        Some(PayloadHeader {
+            compressed: flags & Self::COMPRESSED != 0,
+            encrypted: flags & Self::ENCRYPTED != 0,
+        })
+    }
+}
+
+fn deflate(data: &[u8], prefix: Vec<u8>) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(prefix, Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn inflate(deflated: &[u8]) -> Result<Vec<u8>> {
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(deflated).read_to_end(&mut inflated)?;
+    Ok(inflated)
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| PngError::Crypto(format!("Unable to derive key: {}", e)))?;
+    Ok(Key::from(key))
+}
+
+/// Encrypts `data` with ChaCha20-Poly1305 under a key derived from `password`.
+///
+/// The output is laid out as `prefix | salt | nonce | ciphertext`.
+fn seal(data: &[u8], password: &str, prefix: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::fill(&mut salt).map_err(|e| PngError::Crypto(e.to_string()))?;
+    getrandom::fill(&mut nonce).map_err(|e| PngError::Crypto(e.to_string()))?;
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt)?);
+    let ciphertext = cipher
+        .encrypt(&Nonce::from(nonce), data)
+        .map_err(|_| PngError::Crypto("Unable to encrypt message".to_string()))?;
+
+    let mut container = Vec::with_capacity(prefix.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    container.extend(prefix);
+    container.extend(salt);
+    container.extend(nonce);
+    container.extend(ciphertext);
+    Ok(container)
+}
+
+/// Reverses [`seal`] on the data after its prefix, failing if `password` is
+/// wrong or the data was tampered with.
+fn open(body: &[u8], password: &str) -> Result<Vec<u8>> {
+    if body.len() < SALT_LEN + NONCE_LEN {
+        return Err(PngError::EncryptedTruncated);
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| PngError::EncryptedTruncated)?;
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(password, salt)?);
+    cipher
+        .decrypt(&Nonce::from(nonce), ciphertext)
+        .map_err(|_| PngError::WrongPassword)
+}
+
+/// Applies the optional compression and encryption steps to a message,
+/// prefixing the result with a [`PayloadHeader`]. Plain messages are
+/// returned unchanged, unless they start with something that reads as a
+/// header; those are compressed so they still decode to themselves.
+pub fn encode(data: &[u8], compress: bool, password: Option<&str>) -> Result<Vec<u8>> {
+    let header = PayloadHeader {
+        compressed: compress || (password.is_none() && PayloadHeader::from_bytes(data).is_some()),
+        encrypted: password.is_some(),
+    };
+    if !header.compressed && !header.encrypted {
+        return Ok(data.to_vec());
+    }
+
+    let prefix = header.to_bytes().to_vec();
+    match (header.compressed, password) {
+        (true, Some(password)) => seal(&deflate(data, vec![])?, password, &prefix),
+        (false, Some(password)) => seal(data, password, &prefix),
+        _ => deflate(data, prefix),
+    }
+}
+
+/// Undoes [`encode`], reading the [`PayloadHeader`] to decide which
+/// transforms to reverse. Data without a header is returned as is.
+pub fn decode(data: &[u8], password: Option<&str>) -> Result<Vec<u8>> {
+    let Some(header) = PayloadHeader::from_bytes(data) else {
+        return match password {
+            Some(_) => Err(PngError::NotEncrypted),
+            None => Ok(data.to_vec()),
+        };
+    };
+    let body = &data[PayloadHeader::LEN..];
+    let body = match (header.encrypted, password) {
+        (true, Some(password)) => open(body, password)?,
+        (true, None) => return Err(PngError::PasswordRequired),
+        (false, Some(_)) => return Err(PngError::NotEncrypted),
+        (false, None) => body.to_vec(),
+    };
+    if header.compressed {
+        inflate(&body)
+    } else {
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_round_trip() {
+        let message = "This is where your secret message will be!".repeat(10);
+        let compressed = encode(message.as_bytes(), true, None).unwrap();
+        assert!(compressed.len() < message.len());
+        assert_eq!(decode(&compressed, None).unwrap(), message.as_bytes());
+    }
+
+    #[test]
+    fn test_decode_plain_data() {
+        let message = b"plain message";
+        assert_eq!(decode(message, None).unwrap(), message);
+        assert!(matches!(
+            decode(message, Some("hunter2")),
+            Err(PngError::NotEncrypted)
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let message = b"This is where your secret message will be!";
+        let encrypted = encode(message, false, Some("hunter2")).unwrap();
+        assert!(PayloadHeader::from_bytes(&encrypted).unwrap().encrypted);
+        assert!(matches!(
+            decode(&encrypted, None),
+            Err(PngError::PasswordRequired)
+        ));
+        assert_eq!(decode(&encrypted, Some("hunter2")).unwrap(), message);
+    }
+
+    #[test]
+    fn test_decrypt_wrong_password() {
+        let encrypted = encode(b"secret", false, Some("hunter2")).unwrap();
+        assert!(matches!(
+            decode(&encrypted, Some("hunter3")),
+            Err(PngError::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let message = b"This is where your secret message will be!";
+        let encoded = encode(message, true, Some("hunter2")).unwrap();
+        assert!(decode(&encoded, None).is_err());
+        assert_eq!(decode(&encoded, Some("hunter2")).unwrap(), message);
+    }
+
+    #[test]
+    fn test_payload_header_round_trip() {
+        for (compressed, encrypted) in [(true, false), (false, true), (true, true)] {
+            let header = PayloadHeader {
+                compressed,
+                encrypted,
+            };
+            assert_eq!(PayloadHeader::from_bytes(&header.to_bytes()), Some(header));
+        }
+        assert_eq!(PayloadHeader::from_bytes(b"PM"), None);
+        assert_eq!(PayloadHeader::from_bytes(b"PM\x02\x00"), None);
+        assert_eq!(PayloadHeader::from_bytes(b"PM\x02\x04"), None);
+        assert_eq!(PayloadHeader::from_bytes(b"PMZ\x01"), None);
+    }
+
+    #[test]
+    fn test_encode_writes_header() {
+        let message = "This is where your secret message will be!".repeat(10);
+        let encoded = encode(message.as_bytes(), true, None).unwrap();
+        let header = PayloadHeader::from_bytes(&encoded).unwrap();
+        assert!(header.compressed && !header.encrypted);
+        assert_eq!(decode(&encoded, None).unwrap(), message.as_bytes());
+
+        assert_eq!(encode(b"plain", false, None).unwrap(), b"plain");
+    }
+
+    #[test]
+    fn test_plain_data_that_looks_like_a_header() {
+        for message in [
+            &b"PM\x02\x00A"[..],
+            b"PM\x02\x01A",
+            b"PME\x01A",
+            b"PMZ\x01A",
+        ] {
+            let encoded = encode(message, false, None).unwrap();
+            assert_eq!(decode(&encoded, None).unwrap(), message);
+        }
+    }
+}
@@ -1,51 +1,65 @@
 use crate::chunk::Chunk;
-use crate::Error;
+use crate::chunk_type::ChunkType;
+use crate::parse::ParseOptions;
+use crate::PngError;
 use crate::Result;
 use std::fmt::{Display, Formatter};
+use std::io::{self, Read};
 
 pub struct Png {
+    header: [u8; 8],
     chunks: Vec<Chunk>,
+    trailing: Vec<u8>,
+}
+
+fn is_type(chunk: Option<&Chunk>, chunk_type: &str) -> bool {
+    chunk.is_some_and(|c| c.chunk_type().to_string() == chunk_type)
 }
 
 impl TryFrom<&[u8]> for Png {
-    type Error = Error;
+    type Error = PngError;
 
     fn try_from(value: &[u8]) -> Result<Self> {
-        let mut chunks = vec![];
-        let mut chunks_iter = value.iter();
+        Png::from_reader(value)
+    }
+}
 
-        let header_bytes: Vec<u8> = chunks_iter.by_ref().take(8).copied().collect();
+impl TryFrom<Vec<u8>> for Png {
+    type Error = PngError;
 
-        if Png::STANDARD_HEADER != header_bytes.as_slice() {
-            return Err(Error::from("Invalid header bytes!"));
-        }
+    fn try_from(value: Vec<u8>) -> Result<Self> {
+        Png::try_from(value.as_slice())
+    }
+}
 
-        while chunks_iter.len() >= 12 {
-            let first4: [u8; 4] = chunks_iter
-                .clone()
-                .take(4)
-                .copied()
-                .collect::<Vec<u8>>()
-                .as_slice()
-                .try_into()?;
-            let length = u32::from_be_bytes(first4);
-
-            let chunk_bytes: Vec<u8> = chunks_iter
-                .by_ref()
-                .copied()
-                .take(length as usize + 12)
-                .collect();
-            let chunk = Chunk::try_from(chunk_bytes.as_slice())?;
-            chunks.push(chunk);
+impl Display for Png {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "PNG: {} bytes, {} chunks",
+            self.byte_size(),
+            self.chunk_count()
+        )?;
+        for chunk in self {
+            writeln!(f, "  {} ({} bytes)", chunk.chunk_type(), chunk.length())?;
         }
+        Ok(())
+    }
+}
 
-        Ok(Png { chunks })
+impl<'a> IntoIterator for &'a Png {
+    type Item = &'a Chunk;
+    type IntoIter = std::slice::Iter<'a, Chunk>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.iter()
     }
 }
 
-impl Display for Png {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.as_bytes())
+/// Collects chunks like [`Png::from_chunks`], with the standard signature.
+impl FromIterator<Chunk> for Png {
+    fn from_iter<I: IntoIterator<Item = Chunk>>(iter: I) -> Self {
+        Png::from_chunks(iter.into_iter().collect())
     }
 }
 
@@ -53,12 +67,102 @@ impl Display for Png {
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
 
-    fn from_chunks(chunks: Vec<Chunk>) -> Png {
-        Png { chunks }
+    /// Parses a PNG from `reader` one chunk at a time.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Png> {
+        Png::from_reader_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Like `Png::try_from`, but parsed according to `options`.
+    pub fn parse(bytes: &[u8], options: &ParseOptions) -> Result<Png> {
+        Png::from_reader_with_options(bytes, options)
+    }
+
+    /// Like [`Png::from_reader`], but parsed according to `options`.
+    pub fn from_reader_with_options<R: Read>(mut reader: R, options: &ParseOptions) -> Result<Png> {
+        let mut header = [0u8; 8];
+        match reader.read_exact(&mut header) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(PngError::InvalidSignature)
+            }
+            Err(e) => return Err(PngError::Io(e)),
+            Ok(()) => {}
+        }
+        if options.require_signature && header != Self::STANDARD_HEADER {
+            return Err(PngError::InvalidSignature);
+        }
+
+        let mut chunks = vec![];
+        let mut trailing = vec![];
+        loop {
+            match Chunk::from_reader_with_options(&mut reader, options) {
+                Ok(chunk) => {
+                    if is_type(Some(&chunk), "IHDR") && is_type(chunks.first(), "IHDR") {
+                        return Err(PngError::DuplicateChunk("IHDR".to_string()));
+                    }
+                    chunks.push(chunk)
+                }
+                Err(e) if Chunk::is_end_of_stream(&e) => break,
+                Err(e) => return Err(e),
+            }
+            if is_type(chunks.last(), "IEND") {
+                reader.read_to_end(&mut trailing)?;
+                // A well-formed chunk right after IEND means the chunks are out
+                // of order, anything else is opaque data appended to the file.
+                match Chunk::parse_with_options(&trailing, options) {
+                    Ok(next) if is_type(Some(&next), "IEND") => {
+                        return Err(PngError::DuplicateChunk("IEND".to_string()));
+                    }
+                    Ok(_) => return Err(PngError::MisplacedIend),
+                    Err(_) => break,
+                }
+            }
+        }
+
+        if !is_type(chunks.first(), "IHDR") {
+            return Err(PngError::MissingIhdr);
+        }
+        if options.require_iend && !is_type(chunks.last(), "IEND") {
+            return Err(PngError::MisplacedIend);
+        }
+
+        Ok(Png {
+            header,
+            chunks,
+            trailing,
+        })
+    }
+
+    /// Builds a PNG from chunks in order. The chunks are not validated, so
+    /// callers wanting a well-formed file should start with IHDR and end with IEND.
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
+        Png {
+            header: Self::STANDARD_HEADER,
+            chunks,
+            trailing: vec![],
+        }
     }
 
+    /// Appends `chunk` to the end of the PNG. If the PNG already ends with an
+    /// IEND chunk, the new chunk is inserted just before it instead so that
+    /// IEND stays last.
     pub fn append_chunk(&mut self, chunk: Chunk) {
-        self.chunks.push(chunk);
+        if is_type(self.chunks.last(), "IEND") {
+            self.chunks.insert(self.chunks.len() - 1, chunk);
+        } else {
+            self.chunks.push(chunk);
+        }
+    }
+
+    /// Inserts `chunk` at `index`, keeping any leading IHDR and trailing IEND in place.
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) -> Result<()> {
+        let min = usize::from(is_type(self.chunks.first(), "IHDR"));
+        let max = self.chunks.len() - usize::from(is_type(self.chunks.last(), "IEND"));
+
+        if index < min || index > max {
+            return Err(PngError::IndexOutOfRange { index, min, max });
+        }
+        self.chunks.insert(index, chunk);
+        Ok(())
     }
 
     pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
@@ -69,12 +173,64 @@ impl Png {
         if let Some(position) = position {
             Ok(self.chunks.remove(position))
         } else {
-            Err(Error::from("missing"))
+            Err(PngError::ChunkNotFound(chunk_type.to_string()))
         }
     }
 
-    fn header(&self) -> &[u8; 8] {
-        &Self::STANDARD_HEADER
+    /// Removes the chunk at `index`, refusing to remove a leading IHDR or trailing IEND.
+    pub fn remove_chunk_at(&mut self, index: usize) -> Result<Chunk> {
+        let min = usize::from(is_type(self.chunks.first(), "IHDR"));
+        let end = self.chunks.len() - usize::from(is_type(self.chunks.last(), "IEND"));
+
+        if index < min || index >= end {
+            return Err(PngError::IndexOutOfRange {
+                index,
+                min,
+                max: end.saturating_sub(1),
+            });
+        }
+        Ok(self.chunks.remove(index))
+    }
+
+    pub fn remove_all_chunks(&mut self, chunk_type: &str) -> usize {
+        let before = self.chunks.len();
+        self.chunks
+            .retain(|c| c.chunk_type().to_string() != chunk_type);
+        before - self.chunks.len()
+    }
+
+    /// Replaces the data of the first chunk of `chunk_type`, keeping its position.
+    pub fn replace_chunk(&mut self, chunk_type: &str, new_data: Vec<u8>) -> Result<()> {
+        let chunk = self
+            .chunks
+            .iter_mut()
+            .find(|c| c.chunk_type().to_string() == chunk_type)
+            .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        *chunk = Chunk::new(chunk.chunk_type().clone(), new_data);
+        Ok(())
+    }
+
+    /// Removes every ancillary chunk, returning how many were removed.
+    pub fn strip_ancillary_chunks(&mut self) -> usize {
+        let before = self.chunks.len();
+        self.chunks.retain(|c| c.chunk_type().is_critical());
+        before - self.chunks.len()
+    }
+
+    /// Changes the type of the first chunk of `chunk_type`, keeping its data and position.
+    pub fn rename_chunk(&mut self, chunk_type: &str, new_type: ChunkType) -> Result<()> {
+        let chunk = self
+            .chunks
+            .iter_mut()
+            .find(|c| c.chunk_type().to_string() == chunk_type)
+            .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        *chunk = Chunk::new(new_type, chunk.data().to_vec());
+        Ok(())
+    }
+
+    /// The signature this PNG was read with, written back by [`Png::as_bytes`].
+    pub fn header(&self) -> &[u8; 8] {
+        &self.header
     }
 
     pub fn chunks(&self) -> &[Chunk] {
@@ -87,13 +243,54 @@ impl Png {
             .find(|c| c.chunk_type().to_string() == chunk_type)
     }
 
+    pub fn chunks_by_type(&self, chunk_type: &str) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .filter(|c| c.chunk_type().to_string() == chunk_type)
+            .collect()
+    }
+
+    /// Bytes found after the IEND chunk, re-emitted verbatim by [`Png::as_bytes`].
+    pub fn trailing_bytes(&self) -> &[u8] {
+        &self.trailing
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// The length of [`Png::as_bytes`], computed without serializing.
+    pub fn byte_size(&self) -> usize {
+        self.header().len()
+            + self
+                .chunks
+                .iter()
+                .map(|c| c.length() as usize + 12)
+                .sum::<usize>()
+            + self.trailing.len()
+    }
+
+    /// The byte offset at which each chunk starts in [`Png::as_bytes`].
+    pub fn chunk_offsets(&self) -> Vec<usize> {
+        let mut offset = self.header().len();
+        self.chunks
+            .iter()
+            .map(|c| {
+                let start = offset;
+                offset += c.length() as usize + 12;
+                start
+            })
+            .collect()
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
-        let mut result = vec![];
+        let mut result = Vec::with_capacity(self.byte_size());
         result.extend(self.header());
 
-        for chunk in &self.chunks {
-            result.extend(chunk.as_bytes());
+        for chunk in self {
+            chunk.extend_bytes(&mut result);
         }
+        result.extend(&self.trailing);
         result
     }
 }
@@ -113,6 +310,13 @@ mod tests {
         ]
     }
 
+    fn framed_chunks() -> Vec<Chunk> {
+        let mut chunks = vec![chunk_from_strings("IHDR", "header").unwrap()];
+        chunks.extend(testing_chunks());
+        chunks.push(chunk_from_strings("IEND", "").unwrap());
+        chunks
+    }
+
     fn testing_png() -> Png {
         let chunks = testing_chunks();
         Png::from_chunks(chunks)
@@ -135,9 +339,39 @@ mod tests {
         assert_eq!(png.chunks().len(), 3);
     }
 
+    #[test]
+    fn test_collect_png() {
+        let png: Png = framed_chunks().into_iter().collect();
+        assert_eq!(png.chunks(), Png::from_chunks(framed_chunks()).chunks());
+        assert!(png.as_bytes().starts_with(&Png::STANDARD_HEADER));
+    }
+
+    #[test]
+    fn test_iterate_png() {
+        let png = testing_png();
+        let types: Vec<String> = (&png)
+            .into_iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FrSt", "miDl", "LASt"]);
+
+        let mut count = 0;
+        for _chunk in &png {
+            count += 1;
+        }
+        assert_eq!(count, png.chunk_count());
+    }
+
+    #[test]
+    fn test_from_chunks_round_trip() {
+        let png = Png::from_chunks(framed_chunks());
+        let parsed = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(parsed.chunks(), png.chunks());
+    }
+
     #[test]
     fn test_valid_from_bytes() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
+        let chunk_bytes: Vec<u8> = framed_chunks()
             .into_iter()
             .flat_map(|chunk| chunk.as_bytes())
             .collect();
@@ -153,6 +387,13 @@ mod tests {
         assert!(png.is_ok());
     }
 
+    #[test]
+    fn test_valid_from_vec() {
+        let png = Png::try_from(PNG_FILE.to_vec());
+
+        assert!(png.is_ok());
+    }
+
     #[test]
     fn test_invalid_header() {
         let chunk_bytes: Vec<u8> = testing_chunks()
@@ -171,6 +412,93 @@ mod tests {
         assert!(png.is_err());
     }
 
+    #[test]
+    fn test_jpeg_signature() {
+        let chunk_bytes: Vec<u8> = testing_chunks()
+            .into_iter()
+            .flat_map(|chunk| chunk.as_bytes())
+            .collect();
+
+        let bytes: Vec<u8> = [0xFF, 0xD8, 0xFF, 0xE0, 0, 16, 74, 70]
+            .iter()
+            .chain(chunk_bytes.iter())
+            .copied()
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert_eq!(png.err().unwrap().to_string(), "Invalid PNG signature");
+    }
+
+    #[test]
+    fn test_iend_in_the_middle() {
+        let chunks = [
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
+        ];
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .into_iter()
+            .chain(chunks.iter().flat_map(|chunk| chunk.as_bytes()))
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert_eq!(
+            png.err().unwrap().to_string(),
+            "IEND must be the last chunk"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_ihdr() {
+        let chunks = [
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ];
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .into_iter()
+            .chain(chunks.iter().flat_map(|chunk| chunk.as_bytes()))
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert_eq!(png.err().unwrap().to_string(), "duplicate IHDR chunk");
+    }
+
+    #[test]
+    fn test_duplicate_iend() {
+        let chunks = [
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ];
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .into_iter()
+            .chain(chunks.iter().flat_map(|chunk| chunk.as_bytes()))
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert_eq!(png.err().unwrap().to_string(), "duplicate IEND chunk");
+    }
+
+    #[test]
+    fn test_missing_ihdr() {
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .into_iter()
+            .chain(testing_chunks().iter().flat_map(|chunk| chunk.as_bytes()))
+            .collect();
+
+        let png = Png::try_from(bytes.as_ref());
+
+        assert_eq!(
+            png.err().unwrap().to_string(),
+            "IHDR must be the first chunk"
+        );
+    }
+
     #[test]
     fn test_invalid_chunk() {
         let mut chunk_bytes: Vec<u8> = testing_chunks()
@@ -208,6 +536,20 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
     }
 
+    #[test]
+    fn test_chunks_by_type() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am a second first chunk").unwrap());
+        let chunks = png.chunks_by_type("FrSt");
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(&chunks[0].data_as_string().unwrap(), "I am the first chunk");
+        assert_eq!(
+            &chunks[1].data_as_string().unwrap(),
+            "I am a second first chunk"
+        );
+        assert!(png.chunks_by_type("NoPe").is_empty());
+    }
+
     #[test]
     fn test_append_chunk() {
         let mut png = testing_png();
@@ -217,6 +559,43 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "Message");
     }
 
+    #[test]
+    fn test_append_chunk_before_iend() {
+        let mut png = Png::from_chunks(framed_chunks());
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        let chunks = png.chunks();
+        assert_eq!(&chunks[chunks.len() - 2].chunk_type().to_string(), "TeSt");
+        assert_eq!(&chunks[chunks.len() - 1].chunk_type().to_string(), "IEND");
+    }
+
+    #[test]
+    fn test_insert_chunk() {
+        let mut png = testing_png();
+        png.insert_chunk(1, chunk_from_strings("TeSt", "Message").unwrap())
+            .unwrap();
+        assert_eq!(&png.chunks()[1].chunk_type().to_string(), "TeSt");
+        assert!(png
+            .insert_chunk(5, chunk_from_strings("TeSt", "Message").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_insert_chunk_between_ihdr_and_iend() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        assert!(png
+            .insert_chunk(0, chunk_from_strings("TeSt", "Message").unwrap())
+            .is_err());
+        assert!(png
+            .insert_chunk(2, chunk_from_strings("TeSt", "Message").unwrap())
+            .is_err());
+        png.insert_chunk(1, chunk_from_strings("TeSt", "Message").unwrap())
+            .unwrap();
+        assert_eq!(&png.chunks()[1].chunk_type().to_string(), "TeSt");
+    }
+
     #[test]
     fn test_remove_first_chunk() {
         let mut png = testing_png();
@@ -226,12 +605,157 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_remove_chunk_at() {
+        let mut png = Png::from_chunks(framed_chunks());
+        let removed = png.remove_chunk_at(2).unwrap();
+        assert_eq!(&removed.chunk_type().to_string(), "miDl");
+        assert_eq!(png.chunks().len(), 4);
+
+        assert!(png.remove_chunk_at(0).is_err());
+        assert!(png.remove_chunk_at(3).is_err());
+        assert!(png.remove_chunk_at(10).is_err());
+        assert_eq!(png.chunks().len(), 4);
+    }
+
+    #[test]
+    fn test_remove_all_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "Another").unwrap());
+        assert_eq!(png.remove_all_chunks("TeSt"), 2);
+        assert!(png.chunk_by_type("TeSt").is_none());
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(png.remove_all_chunks("TeSt"), 0);
+    }
+
+    #[test]
+    fn test_strip_ancillary_chunks() {
+        let mut png = Png::from_chunks(framed_chunks());
+        assert_eq!(png.strip_ancillary_chunks(), 1);
+        assert!(png.chunk_by_type("miDl").is_none());
+        assert_eq!(png.chunk_count(), 4);
+        assert_eq!(png.strip_ancillary_chunks(), 0);
+    }
+
+    #[test]
+    fn test_replace_chunk() {
+        let mut png = testing_png();
+        png.replace_chunk("miDl", "I replace the middle chunk".into())
+            .unwrap();
+        let chunk = &png.chunks()[1];
+        assert_eq!(&chunk.chunk_type().to_string(), "miDl");
+        assert_eq!(
+            &chunk.data_as_string().unwrap(),
+            "I replace the middle chunk"
+        );
+        assert_eq!(chunk.length(), 26);
+        assert!(png.replace_chunk("TeSt", vec![]).is_err());
+    }
+
+    #[test]
+    fn test_rename_chunk() {
+        use std::str::FromStr;
+        let mut png = testing_png();
+        png.rename_chunk("miDl", ChunkType::from_str("NeWt").unwrap())
+            .unwrap();
+        let chunk = &png.chunks()[1];
+        assert_eq!(&chunk.chunk_type().to_string(), "NeWt");
+        assert_eq!(&chunk.data_as_string().unwrap(), "I am another chunk");
+        assert!(png.chunk_by_type("miDl").is_none());
+        assert!(png
+            .rename_chunk("TeSt", ChunkType::from_str("NeWt").unwrap())
+            .is_err());
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
         assert!(png.is_ok());
     }
 
+    #[test]
+    fn test_png_from_reader() {
+        let png = Png::from_reader(std::io::Cursor::new(PNG_FILE.to_vec())).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_png_from_truncated_reader() {
+        let png = Png::from_reader(&PNG_FILE[..PNG_FILE.len() - 2]);
+        assert!(png.is_err());
+    }
+
+    #[test]
+    fn test_png_from_failing_reader() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::PermissionDenied))
+            }
+        }
+
+        assert!(matches!(
+            Png::from_reader(Failing),
+            Err(PngError::Io(e)) if e.kind() == io::ErrorKind::PermissionDenied
+        ));
+        assert!(matches!(
+            Png::from_reader(&PNG_FILE[..4]),
+            Err(PngError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_png_from_reader_without_iend() {
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .into_iter()
+            .chain(chunk_from_strings("IHDR", "header").unwrap().as_bytes())
+            .collect();
+
+        assert!(Png::from_reader(bytes.as_slice()).is_err());
+        let options = ParseOptions::default().require_iend(false);
+        let png = Png::from_reader_with_options(bytes.as_slice(), &options).unwrap();
+        assert_eq!(png.chunk_count(), 1);
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[1] = b'X';
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+        let options = ParseOptions::default().require_signature(false);
+        let png = Png::parse(&bytes, &options).unwrap();
+        assert_eq!(png.header()[1], b'X');
+        assert_eq!(png.as_bytes(), bytes);
+
+        // Drop IEND and corrupt the CRC of the chunk before it.
+        bytes.truncate(bytes.len() - 12);
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(Png::parse(&bytes, &options).is_err());
+        let png = Png::parse(&bytes, &ParseOptions::lenient()).unwrap();
+        let original = Png::try_from(PNG_FILE.as_ref()).unwrap();
+        assert_eq!(png.chunks().len(), original.chunks().len() - 1);
+    }
+
+    #[test]
+    fn test_header() {
+        let png = Png::try_from(PNG_FILE.as_ref()).unwrap();
+        assert_eq!(png.header(), &Png::STANDARD_HEADER);
+        assert_eq!(Png::from_chunks(vec![]).header(), &Png::STANDARD_HEADER);
+    }
+
+    #[test]
+    fn test_trailing_bytes() {
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(b"trailing data").copied().collect();
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(png.trailing_bytes(), b"trailing data");
+        assert_eq!(png.as_bytes(), bytes);
+        assert_eq!(png.byte_size(), bytes.len());
+    }
+
     #[test]
     fn test_as_bytes() {
         let png = Png::try_from(&PNG_FILE[..]).unwrap();
@@ -240,9 +764,72 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_chunk_count_and_byte_size() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.chunk_count(), png.chunks().len());
+        assert_eq!(png.byte_size(), PNG_FILE.len());
+    }
+
+    #[test]
+    fn test_chunk_offsets() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let bytes = png.as_bytes();
+        let offsets = png.chunk_offsets();
+        assert_eq!(offsets.len(), png.chunk_count());
+        assert_eq!(offsets[0], 8);
+        for (offset, chunk) in offsets.iter().zip(png.chunks()) {
+            let chunk_type = &bytes[offset + 4..offset + 8];
+            assert_eq!(chunk_type, chunk.chunk_type().bytes());
+        }
+    }
+
+    /// Deterministic xorshift generator so the fuzz tests are reproducible.
+    fn random_bytes(state: &mut u64, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *state ^= *state << 13;
+                *state ^= *state >> 7;
+                *state ^= *state << 17;
+                *state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_random_bytes_never_panic() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for i in 0..2000 {
+            let len = (i % 300) as usize;
+            let mut bytes = random_bytes(&mut state, len);
+            if i % 2 == 0 {
+                bytes.splice(0..0, Png::STANDARD_HEADER);
+            }
+            let _ = Png::try_from(bytes.as_slice());
+            let _ = Chunk::try_from(bytes.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_corrupted_file_never_panics() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        for len in 0..PNG_FILE.len() {
+            let _ = Png::try_from(&PNG_FILE[..len]);
+        }
+        for _ in 0..500 {
+            let mut bytes = PNG_FILE.to_vec();
+            let noise = random_bytes(&mut state, 3);
+            let position = u16::from_be_bytes([noise[0], noise[1]]) as usize % bytes.len();
+            bytes[position] = noise[2];
+            if let Ok(png) = Png::try_from(bytes.as_slice()) {
+                let _ = png.to_string();
+            }
+        }
+    }
+
     #[test]
     fn test_png_trait_impls() {
-        let chunk_bytes: Vec<u8> = testing_chunks()
+        let chunk_bytes: Vec<u8> = framed_chunks()
             .into_iter()
             .flat_map(|chunk| chunk.as_bytes())
             .collect();
@@ -258,6 +845,14 @@ mod tests {
         let _png_string = format!("{}", png);
     }
 
+    #[test]
+    fn test_png_display() {
+        let png = testing_png();
+        let expected =
+            "PNG: 101 bytes, 3 chunks\n  FrSt (20 bytes)\n  miDl (18 bytes)\n  LASt (19 bytes)\n";
+        assert_eq!(png.to_string(), expected);
+    }
+
     // This is the raw bytes for a shrunken version of the `dice.png` image on Wikipedia
     const PNG_FILE: [u8; 4803] = [
         137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 50, 0, 0, 0, 50, 8,
@@ -0,0 +1,53 @@
+use crate::{PngError, Result};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Builds `tEXt` chunk data: a 1-79 byte printable keyword, a null separator
+/// and the text.
+pub fn encode_text(keyword: &str, text: &[u8]) -> Result<Vec<u8>> {
+    let valid =
+        (1..=79).contains(&keyword.len()) && keyword.bytes().all(|b| (0x20..=0x7e).contains(&b));
+    if !valid {
+        return Err(PngError::InvalidKeyword(keyword.to_string()));
+    }
+
+    let mut data = Vec::with_capacity(keyword.len() + 1 + text.len());
+    data.extend_from_slice(keyword.as_bytes());
+    data.push(0);
+    data.extend_from_slice(text);
+    Ok(data)
+}
+
+/// Splits `tEXt` chunk data into its keyword and text, or `None` if there
+/// is no null separator.
+pub fn decode_text(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let separator = data.iter().position(|&b| b == 0)?;
+    Some((&data[..separator], &data[separator + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_round_trip() {
+        let data = encode_text("Comment", b"Created with pngme").unwrap();
+        assert_eq!(data, b"Comment\0Created with pngme");
+        assert_eq!(
+            decode_text(&data),
+            Some((&b"Comment"[..], &b"Created with pngme"[..]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_keyword() {
+        assert!(encode_text("", b"text").is_err());
+        assert!(encode_text("bad\0key", b"text").is_err());
+        assert!(encode_text(&"k".repeat(80), b"text").is_err());
+    }
+
+    #[test]
+    fn test_decode_text_without_separator() {
+        assert_eq!(decode_text(b"no separator"), None);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use std::fmt::{Display, Formatter};
+
+/// A single problem found by [`Png::validate`] or [`Png::validate_bytes`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ValidationError {
+    InvalidSignature,
+    MissingIhdr,
+    MisplacedIend,
+    DuplicateChunk {
+        chunk_type: String,
+        index: usize,
+    },
+    InvalidChunkType {
+        offset: usize,
+    },
+    CrcMismatch {
+        index: usize,
+        expected: u32,
+        actual: u32,
+    },
+    LengthMismatch {
+        index: usize,
+        chunk_type: String,
+        declared: u32,
+        actual: usize,
+    },
+    Truncated {
+        offset: usize,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::InvalidSignature => write!(f, "invalid PNG signature"),
+            ValidationError::MissingIhdr => write!(f, "IHDR is not the first chunk"),
+            ValidationError::MisplacedIend => write!(f, "IEND is not the last chunk"),
+            ValidationError::DuplicateChunk { chunk_type, index } => {
+                write!(f, "chunk {}: duplicate {} chunk", index, chunk_type)
+            }
+            ValidationError::InvalidChunkType { offset } => {
+                write!(f, "invalid chunk type at offset {}", offset)
+            }
+            ValidationError::CrcMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "chunk {}: CRC mismatch, expected {:08x}, found {:08x}",
+                index, expected, actual
+            ),
+            ValidationError::LengthMismatch {
+                index,
+                chunk_type,
+                declared,
+                actual,
+            } => write!(
+                f,
+                "chunk {}: {} declares {} data bytes but holds {}",
+                index, chunk_type, declared, actual
+            ),
+            ValidationError::Truncated { offset } => {
+                write!(f, "truncated chunk at offset {}", offset)
+            }
+        }
+    }
+}
+
+/// Critical chunk types that may appear at most once.
+const UNIQUE_TYPES: [&[u8; 4]; 3] = [b"IHDR", b"PLTE", b"IEND"];
+
+/// Checks the order and uniqueness of critical chunks.
+fn check_order(types: &[[u8; 4]], problems: &mut Vec<ValidationError>) {
+    if types.first() != Some(b"IHDR") {
+        problems.push(ValidationError::MissingIhdr);
+    }
+    if types.last() != Some(b"IEND") {
+        problems.push(ValidationError::MisplacedIend);
+    }
+    for (index, chunk_type) in types.iter().enumerate() {
+        if UNIQUE_TYPES.contains(&chunk_type) && types[..index].contains(chunk_type) {
+            problems.push(ValidationError::DuplicateChunk {
+                chunk_type: String::from_utf8_lossy(chunk_type).into_owned(),
+                index,
+            });
+        }
+    }
+}
+
+impl Png {
+    /// Returns every structural problem with this PNG, or an empty list if it
+    /// is well-formed.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut problems = Vec::new();
+        if self.header() != &Png::STANDARD_HEADER {
+            problems.push(ValidationError::InvalidSignature);
+        }
+        let types: Vec<[u8; 4]> = self
+            .chunks()
+            .iter()
+            .map(|c| c.chunk_type().bytes())
+            .collect();
+        check_order(&types, &mut problems);
+        for (index, chunk) in self.chunks().iter().enumerate() {
+            let expected = Chunk::checksum(chunk.chunk_type(), chunk.data());
+            if expected != chunk.crc() {
+                problems.push(ValidationError::CrcMismatch {
+                    index,
+                    expected,
+                    actual: chunk.crc(),
+                });
+            }
+        }
+        problems
+    }
+
+    /// Like [`Png::validate`], but walks raw bytes so files that don't parse
+    /// can still be diagnosed. Scanning stops at IEND or the first truncated chunk.
+    pub fn validate_bytes(bytes: &[u8]) -> Vec<ValidationError> {
+        let mut problems = Vec::new();
+        if !bytes.starts_with(&Png::STANDARD_HEADER) {
+            problems.push(ValidationError::InvalidSignature);
+        }
+
+        let mut types = Vec::new();
+        let mut offset = Png::STANDARD_HEADER.len();
+        while offset < bytes.len() {
+            let Some(fields) = chunk_fields(bytes, offset) else {
+                problems.push(overrun(bytes, offset, types.len()));
+                break;
+            };
+            let (type_bytes, data, actual) = fields;
+            match ChunkType::try_from(type_bytes) {
+                Ok(chunk_type) => {
+                    let expected = Chunk::checksum(&chunk_type, data);
+                    if expected != actual {
+                        problems.push(ValidationError::CrcMismatch {
+                            index: types.len(),
+                            expected,
+                            actual,
+                        });
+                    }
+                }
+                Err(_) => problems.push(ValidationError::InvalidChunkType { offset }),
+            }
+            types.push(type_bytes);
+            offset += 12 + data.len();
+            if &type_bytes == b"IEND" {
+                break;
+            }
+        }
+
+        check_order(&types, &mut problems);
+        problems
+    }
+}
+
+/// Describes a chunk at `offset` that runs past the end of `bytes`: a
+/// [`ValidationError::LengthMismatch`] if its header is complete but the bytes
+/// left can't hold the declared data and the CRC, otherwise
+/// [`ValidationError::Truncated`].
+fn overrun(bytes: &[u8], offset: usize, index: usize) -> ValidationError {
+    let Some(header) = bytes.get(offset..offset + 8) else {
+        return ValidationError::Truncated { offset };
+    };
+    let declared = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let remaining = bytes.len() - (offset + 8);
+    if (declared as usize).saturating_add(4) > remaining {
+        ValidationError::LengthMismatch {
+            index,
+            chunk_type: String::from_utf8_lossy(&header[4..]).into_owned(),
+            declared,
+            // The last 4 bytes are taken as the CRC.
+            actual: remaining.saturating_sub(4),
+        }
+    } else {
+        ValidationError::Truncated { offset }
+    }
+}
+
+/// The type, data and stored CRC of the chunk at `offset`, or `None` if it is truncated.
+pub fn chunk_fields(bytes: &[u8], offset: usize) -> Option<([u8; 4], &[u8], u32)> {
+    let header = bytes.get(offset..offset + 8)?;
+    let length = u32::from_be_bytes(header[..4].try_into().ok()?) as usize;
+    let chunk_type: [u8; 4] = header[4..].try_into().ok()?;
+    let data_end = (offset + 8).checked_add(length)?;
+    let data = bytes.get(offset + 8..data_end)?;
+    let crc = bytes.get(data_end..data_end + 4)?;
+    Some((chunk_type, data, u32::from_be_bytes(crc.try_into().ok()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.into())
+    }
+
+    fn file_bytes(chunks: &[Chunk]) -> Vec<u8> {
+        Png::STANDARD_HEADER
+            .into_iter()
+            .chain(chunks.iter().flat_map(|c| c.as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_well_formed() {
+        let png = Png::from_chunks(vec![chunk("IHDR", "header"), chunk("IEND", "")]);
+        assert!(png.validate().is_empty());
+        assert!(Png::validate_bytes(&png.as_bytes()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let png = Png::from_chunks(vec![
+            chunk("RuSt", "message"),
+            chunk("IHDR", "header"),
+            chunk("IHDR", "header"),
+        ]);
+        assert_eq!(
+            png.validate(),
+            vec![
+                ValidationError::MissingIhdr,
+                ValidationError::MisplacedIend,
+                ValidationError::DuplicateChunk {
+                    chunk_type: "IHDR".to_string(),
+                    index: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_non_standard_signature() {
+        let mut bytes = file_bytes(&[chunk("IHDR", "header"), chunk("IEND", "")]);
+        bytes[0] = 0;
+        let png = Png::parse(&bytes, &crate::parse::ParseOptions::lenient()).unwrap();
+        assert_eq!(png.validate(), vec![ValidationError::InvalidSignature]);
+    }
+
+    #[test]
+    fn test_validate_bytes_corrupt_file() {
+        let mut bytes = file_bytes(&[
+            chunk("IHDR", "header"),
+            chunk("RuSt", "message"),
+            chunk("IEND", ""),
+        ]);
+        bytes[0] = 0;
+        // Flip a data byte in IHDR and in RuSt.
+        bytes[16] ^= 1;
+        bytes[8 + 18 + 8] ^= 1;
+
+        let problems = Png::validate_bytes(&bytes);
+        assert_eq!(problems.len(), 3);
+        assert_eq!(problems[0], ValidationError::InvalidSignature);
+        assert!(matches!(
+            problems[1],
+            ValidationError::CrcMismatch { index: 0, .. }
+        ));
+        assert!(matches!(
+            problems[2],
+            ValidationError::CrcMismatch { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_validate_bytes_truncated() {
+        let mut bytes = file_bytes(&[chunk("IHDR", "header"), chunk("IEND", "")]);
+        // Cut IEND off part way through its length and type.
+        bytes.truncate(bytes.len() - 8);
+        let problems = Png::validate_bytes(&bytes);
+        assert!(problems.contains(&ValidationError::Truncated { offset: 26 }));
+        assert!(problems.contains(&ValidationError::MisplacedIend));
+    }
+
+    #[test]
+    fn test_validate_bytes_length_mismatch() {
+        let mut bytes = file_bytes(&[chunk("IHDR", "header"), chunk("RuSt", "message")]);
+        // Declare 40 data bytes for RuSt, which only has 7 bytes plus its CRC left.
+        bytes[8 + 18 + 3] = 40;
+        let problems = Png::validate_bytes(&bytes);
+        assert!(problems.contains(&ValidationError::LengthMismatch {
+            index: 1,
+            chunk_type: "RuSt".to_string(),
+            declared: 40,
+            actual: 7,
+        }));
+        assert!(problems.contains(&ValidationError::MisplacedIend));
+
+        // Declaring 9 leaves room for the data but not the CRC.
+        bytes[8 + 18 + 3] = 9;
+        assert!(
+            Png::validate_bytes(&bytes).contains(&ValidationError::LengthMismatch {
+                index: 1,
+                chunk_type: "RuSt".to_string(),
+                declared: 9,
+                actual: 7,
+            })
+        );
+    }
+}
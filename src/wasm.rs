@@ -0,0 +1,69 @@
+//! JavaScript bindings over the in-memory [`Png`] API, built with the `wasm` feature.
+//!
+//! Build the module with
+//! `cargo rustc --lib --crate-type cdylib --target wasm32-unknown-unknown --no-default-features --features wasm`
+//! and generate the JS glue with `wasm-bindgen`.
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use crate::{payload, PngError, Result};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// Returns `png` with `message` stored in a new `chunk_type` chunk before IEND.
+#[wasm_bindgen]
+pub fn encode(
+    png: &[u8],
+    chunk_type: &str,
+    message: &str,
+) -> std::result::Result<Vec<u8>, JsError> {
+    encode_message(png, chunk_type, message).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// The message in the first `chunk_type` chunk of `png`.
+#[wasm_bindgen]
+pub fn decode(png: &[u8], chunk_type: &str) -> std::result::Result<String, JsError> {
+    decode_message(png, chunk_type).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn encode_message(png: &[u8], chunk_type: &str, message: &str) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png)?;
+    let chunk_type = ChunkType::from_str(chunk_type)?;
+    png.append_chunk(Chunk::new(chunk_type, message.as_bytes().to_vec()));
+    Ok(png.as_bytes())
+}
+
+fn decode_message(png: &[u8], chunk_type: &str) -> Result<String> {
+    let png = Png::try_from(png)?;
+    let chunk = png
+        .chunk_by_type(chunk_type)
+        .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+    let message = payload::decode(chunk.data(), None)?;
+    String::from_utf8(message).map_err(|e| PngError::InvalidUtf8(e.utf8_error()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), b"header".to_vec()),
+            Chunk::iend(),
+        ])
+        .as_bytes();
+
+        let encoded = encode_message(&png, "ruSt", "from the browser").unwrap();
+        assert_eq!(
+            decode_message(&encoded, "ruSt").unwrap(),
+            "from the browser"
+        );
+        assert!(matches!(
+            decode_message(&png, "ruSt"),
+            Err(PngError::ChunkNotFound(_))
+        ));
+        assert!(encode_message(b"not a png", "ruSt", "").is_err());
+    }
+}
@@ -0,0 +1,564 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use common::{pngme, scratch_copy, stdout};
+use pngme::png::Png;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_print_lists_chunks() {
+    let path = common::fixture("red-1x1.png");
+    let output = pngme(&["print", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    let lines: Vec<String> = stdout(&output).lines().map(str::to_owned).collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("IHDR"));
+    assert!(lines[2].starts_with("IDAT"));
+    assert!(lines[3].starts_with("IEND"));
+}
+
+#[test]
+fn test_encode_decode_remove() {
+    let path = scratch_copy("red-1x1.png", "encode_decode_remove");
+    let path = path.to_str().unwrap();
+
+    let output = pngme(&["-q", "encode", path, "ruSt", "hidden message"]);
+    assert!(output.status.success());
+    let png = Png::try_from(fs::read(path).unwrap().as_slice()).unwrap();
+    assert_eq!(png.chunks().len(), 4);
+    assert_eq!(png.chunks()[2].chunk_type().to_string(), "ruSt");
+
+    let output = pngme(&["decode", path, "ruSt"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("hidden message"));
+
+    let output = pngme(&["-q", "remove", path, "ruSt"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read(path).unwrap(), common::load_fixture("red-1x1.png"));
+
+    let output = pngme(&["decode", path, "ruSt"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn test_encode_to_output_leaves_input_untouched() {
+    let path = common::fixture("sample-blue-100x75.png");
+    let out = scratch_copy("sample-blue-100x75.png", "encode_output");
+    let output = pngme(&[
+        "-q",
+        "encode",
+        path.to_str().unwrap(),
+        "ruSt",
+        "message",
+        "-o",
+        out.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        common::load_fixture("sample-blue-100x75.png")
+    );
+    let png = Png::try_from(fs::read(&out).unwrap().as_slice()).unwrap();
+    assert_eq!(
+        png.chunk_by_type("ruSt").unwrap().data_as_bytes(),
+        b"message"
+    );
+}
+
+#[test]
+fn test_decode_pattern_matches_every_type() {
+    let path = scratch_copy("red-1x1.png", "decode_pattern");
+    let path = path.to_str().unwrap();
+    assert!(pngme(&["-q", "encode", path, "ruSt", "one"])
+        .status
+        .success());
+    assert!(pngme(&["-q", "encode", path, "rust", "two"])
+        .status
+        .success());
+
+    let output = pngme(&["decode", path, "ru.t"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "ruSt: one\nrust: two\n");
+}
+
+#[test]
+fn test_default_chunk_type_from_env() {
+    let path = scratch_copy("red-1x1.png", "default_chunk_type");
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_pngme"))
+            .args(args)
+            .env("PNGME_CHUNK_TYPE", "ruSt")
+            .env("MESSAGE", "from env")
+            .output()
+            .unwrap()
+    };
+    let path = path.to_str().unwrap();
+
+    assert!(run(&["-q", "encode", path, "--message-env", "MESSAGE"])
+        .status
+        .success());
+    assert_eq!(
+        stdout(&run(&["decode", path])),
+        "Hidden message is: from env\n"
+    );
+    // A lone positional is the chunk type, not a message for the default type.
+    assert!(!run(&["-q", "encode", path, "abCd"]).status.success());
+    assert!(run(&["-q", "remove", path]).status.success());
+    assert_eq!(fs::read(path).unwrap(), common::load_fixture("red-1x1.png"));
+}
+
+#[test]
+fn test_export_round_trips_message_file() {
+    let path = scratch_copy("red-1x1.png", "export");
+    let path = path.to_str().unwrap();
+    let binary = common::fixture("sample-blue-100x75.png");
+    let out = path.replace(".png", ".bin");
+
+    let output = pngme(&[
+        "-q",
+        "encode",
+        path,
+        "ruSt",
+        "--message-file",
+        binary.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(pngme(&["-q", "export", path, "ruSt", &out])
+        .status
+        .success());
+    assert_eq!(fs::read(&out).unwrap(), fs::read(&binary).unwrap());
+
+    assert_eq!(
+        pngme(&["export", path, "zzZz", &out]).status.code(),
+        Some(4)
+    );
+}
+
+#[test]
+fn test_base64_round_trip() {
+    let path = common::fixture("sample-blue-100x75.png");
+    let out = scratch_copy("red-1x1.png", "base64");
+    let text = scratch_copy("red-1x1.png", "base64.txt");
+
+    let output = pngme(&["tobase64", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    fs::write(&text, &output.stdout).unwrap();
+
+    let output = pngme(&[
+        "-q",
+        "frombase64",
+        text.to_str().unwrap(),
+        out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert_eq!(fs::read(&out).unwrap(), fs::read(&path).unwrap());
+}
+
+#[test]
+fn test_survey_skips_unparseable_files() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("survey");
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(common::fixture("red-1x1.png"), dir.join("a.png")).unwrap();
+    fs::copy(common::fixture("sample-blue-100x75.png"), dir.join("b.png")).unwrap();
+    fs::write(dir.join("broken.png"), b"not a png").unwrap();
+
+    let output = pngme(&["survey", dir.to_str().unwrap()]);
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text
+        .lines()
+        .any(|line| line.split_whitespace().eq(["IHDR", "2", "2"])));
+    assert!(text.contains("2 of 3 file(s) surveyed"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("broken.png"));
+}
+
+#[test]
+fn test_repl_writes_only_on_save() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let path = scratch_copy("red-1x1.png", "repl");
+    let run = |script: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_pngme"))
+            .args(["repl", path.to_str().unwrap()])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(script.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = run("add ruSt hello there\ndecode ruSt\nquit\n");
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("Hidden message is: hello there"));
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        common::load_fixture("red-1x1.png")
+    );
+
+    let output = run("add ruSt saved\nsave\n");
+    assert!(output.status.success());
+    let png = Png::try_from(fs::read(&path).unwrap().as_slice()).unwrap();
+    assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"saved");
+
+    let saved = fs::read(&path).unwrap();
+    let output = run("rm IEND\nrm IHDR\nsave\n");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr)
+            .matches("out of range")
+            .count(),
+        2
+    );
+    assert_eq!(fs::read(&path).unwrap(), saved);
+
+    assert!(!pngme(&["repl", "-"]).status.success());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_in_place_write_keeps_mode_and_symlink() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let path = scratch_copy("red-1x1.png", "keeps_mode");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let link = path.with_file_name("keeps_mode-link.png");
+    let _ = fs::remove_file(&link);
+    symlink(&path, &link).unwrap();
+
+    let output = pngme(&["-q", "encode", link.to_str().unwrap(), "ruSt", "hi"]);
+    assert!(output.status.success());
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+    let png = Png::try_from(fs::read(&path).unwrap().as_slice()).unwrap();
+    assert!(png.chunk_by_type("ruSt").is_some());
+}
+
+#[test]
+fn test_verify_exit_codes() {
+    let path = scratch_copy("red-1x1.png", "verify_codes");
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[0] = 0;
+    fs::write(&path, &bytes).unwrap();
+    assert_eq!(
+        pngme(&["verify", path.to_str().unwrap()]).status.code(),
+        Some(3)
+    );
+
+    bytes[0] = 137;
+    bytes[20] ^= 1;
+    fs::write(&path, &bytes).unwrap();
+    assert_eq!(
+        pngme(&["verify", path.to_str().unwrap()]).status.code(),
+        Some(5)
+    );
+}
+
+#[test]
+fn test_verify_ignores_data_after_iend() {
+    let path = scratch_copy("red-1x1.png", "verify_trailing");
+    let mut bytes = fs::read(&path).unwrap();
+    bytes.extend_from_slice(b"appended");
+    fs::write(&path, &bytes).unwrap();
+
+    let output = pngme(&["verify", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(stdout(&output).ends_with("All chunks OK\n"));
+}
+
+#[test]
+fn test_usage_errors_exit_6() {
+    let output = pngme(&["info", "ab"]);
+    assert_eq!(output.status.code(), Some(6));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid chunk type 'ab'"));
+
+    assert_eq!(pngme(&["nosuchcmd"]).status.code(), Some(6));
+    assert_eq!(pngme(&["encode"]).status.code(), Some(6));
+}
+
+#[test]
+fn test_help_and_version_exit_0() {
+    for flag in ["--help", "--version"] {
+        let output = pngme(&[flag]);
+        assert_eq!(output.status.code(), Some(0));
+        assert!(!stdout(&output).is_empty());
+    }
+}
+
+#[test]
+fn test_quiet_encode_suppresses_warnings() {
+    let path = scratch_copy("red-1x1.png", "quiet_encode");
+    let path = path.to_str().unwrap();
+
+    let output = pngme(&["encode", path, "RuSt", "critical"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("critical chunk type"));
+
+    let output = pngme(&["-q", "encode", path, "RuSt", "critical", "--force"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_plain_message_that_looks_like_a_payload_header() {
+    let path = scratch_copy("red-1x1.png", "payload_lookalike");
+    let path = path.to_str().unwrap();
+    for hex in ["504d020041", "504d020141", "504d450141"] {
+        assert!(
+            pngme(&["-q", "encode", path, "ruSt", "--hex", hex, "--force"])
+                .status
+                .success()
+        );
+    }
+
+    let output = pngme(&["decode", path, "ruSt", "--all", "--hex"]);
+    assert!(output.status.success());
+    let output = stdout(&output);
+    for dump in ["50 4d 02 00 41 ", "50 4d 02 01 41 ", "50 4d 45 01 41 "] {
+        assert!(output.contains(dump), "{} missing from {}", dump, output);
+    }
+}
+
+#[test]
+fn test_copy_exit_codes_for_bad_destination() {
+    let src = common::fixture("red-1x1.png");
+    let src = src.to_str().unwrap();
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+
+    let missing = dir.join("copy_missing_dst.png");
+    let _ = fs::remove_file(&missing);
+    let output = pngme(&["copy", src, missing.to_str().unwrap(), "IHDR"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let not_png = dir.join("copy_not_png.txt");
+    fs::write(&not_png, "not a png").unwrap();
+    let output = pngme(&["copy", src, not_png.to_str().unwrap(), "IHDR"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("copy_not_png.txt"));
+}
+
+#[test]
+fn test_merge_exit_codes_for_bad_other_file() {
+    let base = common::fixture("red-1x1.png");
+    let base = base.to_str().unwrap();
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let output_path = dir.join("merge_bad_other_out.png");
+    let output_path = output_path.to_str().unwrap();
+
+    let missing = dir.join("merge_missing_other.png");
+    let _ = fs::remove_file(&missing);
+    let output = pngme(&["merge", base, missing.to_str().unwrap(), "-o", output_path]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let not_png = dir.join("merge_not_png.txt");
+    fs::write(&not_png, "not a png").unwrap();
+    let output = pngme(&["merge", base, not_png.to_str().unwrap(), "-o", output_path]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("merge_not_png.txt"));
+}
+
+#[test]
+fn test_validate_exit_codes_match_verify() {
+    let path = scratch_copy("red-1x1.png", "validate_codes");
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[20] ^= 1;
+    fs::write(&path, &bytes).unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(pngme(&["validate", path]).status.code(), Some(5));
+    assert_eq!(pngme(&["verify", path]).status.code(), Some(5));
+
+    bytes[0] = 0;
+    fs::write(path, &bytes).unwrap();
+    assert_eq!(pngme(&["validate", path]).status.code(), Some(3));
+}
+
+#[test]
+fn test_print_directory_reports_io_error() {
+    let output = pngme(&["print", env!("CARGO_TARGET_TMPDIR")]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("signature"));
+}
+
+#[test]
+fn test_dry_run_leaves_file_untouched() {
+    let path = scratch_copy("red-1x1.png", "dry_run");
+    let before = fs::read(&path).unwrap();
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = pngme(&["encode", path, "ruSt", "message", "--dry-run"]);
+    assert!(output.status.success());
+    assert!(!stdout(&output).is_empty());
+    assert!(pngme(&["remove", path, "IDAT", "--dry-run"])
+        .status
+        .success());
+
+    assert_eq!(fs::read(path).unwrap(), before);
+    assert_eq!(fs::metadata(path).unwrap().modified().unwrap(), modified);
+}
+
+#[test]
+fn test_backup_keeps_original_bytes() {
+    let path = scratch_copy("red-1x1.png", "backup");
+    let original = fs::read(&path).unwrap();
+    let bak = path.with_extension("png.bak");
+    let _ = fs::remove_file(&bak);
+    let path_str = path.to_str().unwrap();
+
+    assert!(
+        pngme(&["-q", "encode", path_str, "ruSt", "new", "--backup"])
+            .status
+            .success()
+    );
+    assert_eq!(fs::read(&bak).unwrap(), original);
+    let png = Png::try_from(fs::read(&path).unwrap().as_slice()).unwrap();
+    assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"new");
+
+    fs::remove_file(&bak).unwrap();
+    let out = path.with_file_name("backup-other.png");
+    let output = pngme(&[
+        "-q",
+        "remove",
+        path_str,
+        "ruSt",
+        "--backup",
+        "-o",
+        out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(!bak.exists());
+    assert!(!out.with_extension("png.bak").exists());
+    assert_eq!(fs::read(&out).unwrap(), original);
+}
+
+#[test]
+fn test_batch_remove_rewrites_only_pngs() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("batch_remove");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let fixtures = ["red-1x1.png", "sample-blue-100x75.png"];
+    for name in fixtures {
+        let path = dir.join(name);
+        fs::copy(common::fixture(name), &path).unwrap();
+        assert!(
+            pngme(&["-q", "encode", path.to_str().unwrap(), "ruSt", "x"])
+                .status
+                .success()
+        );
+    }
+    fs::write(dir.join("notes.txt"), b"ruSt").unwrap();
+
+    let output = pngme(&["batch-remove", dir.to_str().unwrap(), "ruSt"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("2 of 2 file(s) modified"));
+    for name in fixtures {
+        assert_eq!(
+            fs::read(dir.join(name)).unwrap(),
+            common::load_fixture(name)
+        );
+    }
+    assert_eq!(fs::read(dir.join("notes.txt")).unwrap(), b"ruSt");
+}
+
+#[test]
+fn test_copy_appends_chunk_to_destination() {
+    let src = scratch_copy("red-1x1.png", "copy_src");
+    let dst = scratch_copy("sample-blue-100x75.png", "copy_dst");
+    let src = src.to_str().unwrap();
+    let dst = dst.to_str().unwrap();
+    assert!(pngme(&["-q", "encode", src, "ruSt", "copied"])
+        .status
+        .success());
+
+    assert!(pngme(&["-q", "copy", src, dst, "ruSt"]).status.success());
+    let png = Png::try_from(fs::read(dst).unwrap().as_slice()).unwrap();
+    assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"copied");
+    assert_eq!(
+        png.chunks().last().unwrap().chunk_type().to_string(),
+        "IEND"
+    );
+
+    assert_eq!(pngme(&["copy", src, dst, "zzZz"]).status.code(), Some(4));
+}
+
+#[test]
+fn test_merge_appends_custom_chunks() {
+    let base = common::fixture("sample-blue-100x75.png");
+    let other = scratch_copy("red-1x1.png", "merge_other");
+    let other = other.to_str().unwrap();
+    let out = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("merge_out.png");
+    assert!(
+        pngme(&["-q", "encode", other, "ruSt", "one", "--chunk", "abCd=two"])
+            .status
+            .success()
+    );
+
+    let output = pngme(&[
+        "merge",
+        base.to_str().unwrap(),
+        other,
+        "-o",
+        out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("2 chunk(s) merged"));
+
+    let merged = Png::try_from(fs::read(&out).unwrap().as_slice()).unwrap();
+    let base = Png::try_from(fs::read(&base).unwrap().as_slice()).unwrap();
+    assert_eq!(merged.chunks().len(), base.chunks().len() + 2);
+    assert_eq!(merged.chunk_by_type("ruSt").unwrap().data(), b"one");
+    assert_eq!(merged.chunk_by_type("abCd").unwrap().data(), b"two");
+    assert_eq!(
+        merged.chunk_by_type("IDAT").unwrap().data(),
+        base.chunk_by_type("IDAT").unwrap().data()
+    );
+}
+
+#[test]
+fn test_extract_all_to_directory_and_file() {
+    let path = scratch_copy("red-1x1.png", "extract_all");
+    let path = path.to_str().unwrap();
+    assert!(
+        pngme(&["-q", "encode", path, "ruSt", "one", "--chunk", "abCd=two"])
+            .status
+            .success()
+    );
+
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("extract_all");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let output = pngme(&["extract-all", path, dir.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("2 chunk(s) extracted!"));
+    assert_eq!(fs::read(dir.join("2-ruSt.bin")).unwrap(), b"one");
+    assert_eq!(fs::read(dir.join("3-abCd.bin")).unwrap(), b"two");
+
+    let file = dir.join("all.txt");
+    let file = file.to_str().unwrap();
+    assert!(pngme(&["-q", "extract-all", path, file]).status.success());
+    assert_eq!(fs::read(file).unwrap(), b"one\ntwo\n");
+    assert!(
+        pngme(&["-q", "extract-all", path, file, "--chunk-type", "abCd"])
+            .status
+            .success()
+    );
+    assert_eq!(fs::read(file).unwrap(), b"two\n");
+
+    let output = pngme(&["extract-all", path, file, "--chunk-type", "zzZz"]);
+    assert_eq!(output.status.code(), Some(4));
+}
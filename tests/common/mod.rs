@@ -0,0 +1,38 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The path of a sample PNG in `tests/fixtures`.
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// Reads a sample PNG from `tests/fixtures`.
+#[allow(dead_code)]
+pub fn load_fixture(name: &str) -> Vec<u8> {
+    fs::read(fixture(name)).unwrap()
+}
+
+/// Copies a fixture to a scratch path unique to `test`, so commands can edit it in place.
+#[allow(dead_code)]
+pub fn scratch_copy(name: &str, test: &str) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}-{}", test, name));
+    fs::copy(fixture(name), &path).unwrap();
+    path
+}
+
+/// Runs the pngme binary with `args`.
+#[allow(dead_code)]
+pub fn pngme(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pngme"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[allow(dead_code)]
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
@@ -0,0 +1,16 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use pngme::png::Png;
+
+#[test]
+fn test_sample_png_round_trip() {
+    for name in ["sample-blue-100x75.png", "red-1x1.png"] {
+        let bytes = common::load_fixture(name);
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(png.as_bytes(), bytes);
+    }
+}